@@ -1,5 +1,7 @@
 use std::fs;
 
+use numtheory::chinese_remainder;
+
 fn main() {
     let contents = fs::read_to_string(
         "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day13/input.txt",
@@ -13,10 +15,10 @@ fn main() {
     println!("{}", solve_part2("67,7,59,61"));
 
     println!("{}", solve_part1(&lines));
-    println!("{}", solve_part2(&lines[1]));
+    println!("{}", solve_part2(lines[1]));
 }
 
-fn solve_part1(lines: &Vec<&str>) -> i64 {
+fn solve_part1(lines: &[&str]) -> i64 {
     let start_time: i64 = lines[0].parse().unwrap();
     let depart_intervals: Vec<i64> = lines[1]
         .split(",")
@@ -35,52 +37,29 @@ fn solve_part1(lines: &Vec<&str>) -> i64 {
     }
 }
 
+// The earliest time t at which each listed bus departs as many minutes after t as its
+// position in the list, i.e. t == -position (mod id) for every bus.
 fn solve_part2(line: &str) -> i64 {
-    let depart_intervals: Vec<i64> = line.split(",").map(|x| x.parse().unwrap_or(-1)).collect();
-
-    let mut sub: i64 = 0;
-    let mut current_increment = depart_intervals[0];
-    assert!(current_increment != -1);
-
-    for (offset, next_id) in depart_intervals[1..depart_intervals.len()]
-        .iter()
-        .cloned()
+    let congruences: Vec<(i128, i128)> = line
+        .split(',')
         .enumerate()
-    {
-        if next_id == -1 {
-            continue;
-        }
-        let mut mult: i64 = 1;
-        let minutes_later = ((offset + 1) as i64 + next_id - (sub % next_id)) % next_id;
-
-        loop {
-            let remainder = (mult * current_increment) % next_id;
-            if minutes_later == remainder {
-                sub += mult * current_increment;
-                current_increment = lcm(current_increment, next_id);
-                break;
-            }
-            mult += 1;
-        }
-    }
+        .filter(|&(_, id)| id != "x")
+        .map(|(offset, id)| (-(offset as i128), id.parse().unwrap()))
+        .collect();
 
-    current_increment - sub
+    let (earliest_time, _) = chinese_remainder(&congruences).unwrap();
+    earliest_time as i64
 }
 
-fn gcd(a: i64, b: i64) -> i64 {
-    if a < b {
-        return gcd(b, a);
-    } else {
-        let rem = a % b;
-        if rem == 0 {
-            b
-        } else {
-            gcd(b, rem)
-        }
-    }
-}
+#[cfg(test)]
+mod tests {
+    use super::solve_part2;
 
-fn lcm(a: i64, b: i64) -> i64 {
-    let common = gcd(a, b);
-    return a / common * b;
+    #[test]
+    fn test_part2_samples() {
+        assert_eq!(1068781, solve_part2("7,13,x,x,59,x,31,19"));
+        assert_eq!(3417, solve_part2("17,x,13,19"));
+        assert_eq!(754018, solve_part2("67,7,59,61"));
+        assert_eq!(1202161486, solve_part2("1789,37,47,1889"));
+    }
 }
@@ -0,0 +1,128 @@
+use textformat::json_string;
+
+use crate::rules::FieldRule;
+
+// Tickets with their values reordered by field, using the solved field -> column mapping.
+#[derive(Debug)]
+pub struct DecodedTickets<'a> {
+    pub field_names: Vec<&'a str>,
+    pub your_ticket: Vec<i64>,
+    pub nearby_tickets: Vec<Vec<i64>>,
+}
+
+fn decode_ticket(mapping: &[usize], ticket: &[i64]) -> Vec<i64> {
+    mapping.iter().map(|&column| ticket[column]).collect()
+}
+
+pub fn decode_tickets<'a>(
+    rules: &[FieldRule<'a>],
+    mapping: &[usize],
+    your_ticket: &[i64],
+    nearby_tickets: &[Vec<i64>],
+) -> DecodedTickets<'a> {
+    DecodedTickets {
+        field_names: rules.iter().map(|rule| rule.name).collect(),
+        your_ticket: decode_ticket(mapping, your_ticket),
+        nearby_tickets: nearby_tickets
+            .iter()
+            .map(|ticket| decode_ticket(mapping, ticket))
+            .collect(),
+    }
+}
+
+// Quotes a CSV cell only when it needs it, as RFC 4180 describes.
+fn csv_cell(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl<'a> DecodedTickets<'a> {
+    fn json_object(&self, values: &[i64]) -> String {
+        let pairs: Vec<String> = self
+            .field_names
+            .iter()
+            .zip(values.iter())
+            .map(|(name, value)| format!("{}: {}", json_string(name), value))
+            .collect();
+        format!("{{{}}}", pairs.join(", "))
+    }
+
+    pub fn to_json(&self) -> String {
+        let nearby: Vec<String> = self
+            .nearby_tickets
+            .iter()
+            .map(|ticket| format!("    {}", self.json_object(ticket)))
+            .collect();
+
+        format!(
+            "{{\n  \"your_ticket\": {},\n  \"nearby_tickets\": [\n{}\n  ]\n}}\n",
+            self.json_object(&self.your_ticket),
+            nearby.join(",\n")
+        )
+    }
+
+    // One row per ticket, yours first, with a "ticket" column telling them apart.
+    pub fn to_csv(&self) -> String {
+        let mut header = vec![String::from("ticket")];
+        header.extend(self.field_names.iter().map(|name| csv_cell(name)));
+        let mut lines = vec![header.join(",")];
+
+        let rows = std::iter::once(("yours", &self.your_ticket))
+            .chain(self.nearby_tickets.iter().map(|ticket| ("nearby", ticket)));
+        for (kind, values) in rows {
+            let mut row = vec![kind.to_string()];
+            row.extend(values.iter().map(|value| value.to_string()));
+            lines.push(row.join(","));
+        }
+
+        lines.join("\n") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{csv_cell, decode_tickets};
+    use crate::rules::FieldRule;
+
+    fn make_sample() -> (Vec<FieldRule<'static>>, Vec<usize>) {
+        // the part 2 sample: row is column 0, class is column 1, seat is column 2
+        let rules = vec![
+            FieldRule::parse("class: 0-1 or 4-19"),
+            FieldRule::parse("departure row: 0-5 or 8-19"),
+            FieldRule::parse("seat: 0-13 or 16-19"),
+        ];
+        (rules, vec![1, 0, 2])
+    }
+
+    #[test]
+    fn test_json_export() {
+        let (rules, mapping) = make_sample();
+        let decoded = decode_tickets(&rules, &mapping, &[11, 12, 13], &[vec![3, 9, 18]]);
+
+        assert_eq!(vec![12, 11, 13], decoded.your_ticket);
+        assert_eq!(
+            "{\n  \"your_ticket\": {\"class\": 12, \"departure row\": 11, \"seat\": 13},\n  \"nearby_tickets\": [\n    {\"class\": 9, \"departure row\": 3, \"seat\": 18}\n  ]\n}\n",
+            decoded.to_json()
+        );
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let (rules, mapping) = make_sample();
+        let decoded = decode_tickets(
+            &rules,
+            &mapping,
+            &[11, 12, 13],
+            &[vec![3, 9, 18], vec![15, 1, 5]],
+        );
+
+        assert_eq!(
+            "ticket,class,departure row,seat\nyours,12,11,13\nnearby,9,3,18\nnearby,1,15,5\n",
+            decoded.to_csv()
+        );
+        assert_eq!("\"a, \"\"b\"\"\"", csv_cell("a, \"b\""));
+    }
+}
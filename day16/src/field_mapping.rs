@@ -0,0 +1,337 @@
+use std::collections::VecDeque;
+
+const UNMATCHED: usize = usize::MAX;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldMapping {
+    // field index -> ticket column index
+    Unique(Vec<usize>),
+    // One valid mapping, plus the other columns each ambiguous field could also occupy.
+    Ambiguous {
+        mapping: Vec<usize>,
+        alternatives: Vec<(usize, Vec<usize>)>,
+    },
+    // These fields only fit in fewer columns than there are fields, so they can't all be placed.
+    Impossible {
+        fields: Vec<usize>,
+        columns: Vec<usize>,
+    },
+}
+
+// Hopcroft-Karp maximum bipartite matching between fields and ticket columns.
+struct Matcher<'a> {
+    field_to_columns: &'a [Vec<usize>],
+    field_match: Vec<usize>,
+    column_match: Vec<usize>,
+    distance: Vec<usize>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(field_to_columns: &'a [Vec<usize>], column_count: usize) -> Matcher<'a> {
+        Matcher {
+            field_to_columns,
+            field_match: vec![UNMATCHED; field_to_columns.len()],
+            column_match: vec![UNMATCHED; column_count],
+            distance: vec![0; field_to_columns.len()],
+        }
+    }
+
+    // Layers the fields by alternating path length from the unmatched ones. Returns whether
+    // any augmenting path exists.
+    fn build_layers(&mut self) -> bool {
+        let mut queue = VecDeque::new();
+        for field in 0..self.field_match.len() {
+            if self.field_match[field] == UNMATCHED {
+                self.distance[field] = 0;
+                queue.push_back(field);
+            } else {
+                self.distance[field] = usize::MAX;
+            }
+        }
+
+        let mut found_augmenting_path = false;
+        while let Some(field) = queue.pop_front() {
+            for &column in self.field_to_columns[field].iter() {
+                let next_field = self.column_match[column];
+                if next_field == UNMATCHED {
+                    found_augmenting_path = true;
+                } else if self.distance[next_field] == usize::MAX {
+                    self.distance[next_field] = self.distance[field] + 1;
+                    queue.push_back(next_field);
+                }
+            }
+        }
+
+        found_augmenting_path
+    }
+
+    fn augment(&mut self, field: usize) -> bool {
+        for index in 0..self.field_to_columns[field].len() {
+            let column = self.field_to_columns[field][index];
+            let next_field = self.column_match[column];
+            let can_augment = next_field == UNMATCHED
+                || (self.distance[next_field] == self.distance[field] + 1
+                    && self.augment(next_field));
+
+            if can_augment {
+                self.field_match[field] = column;
+                self.column_match[column] = field;
+                return true;
+            }
+        }
+
+        self.distance[field] = usize::MAX; // dead end, don't try it again in this phase
+        false
+    }
+
+    fn run(&mut self) {
+        while self.build_layers() {
+            for field in 0..self.field_match.len() {
+                if self.field_match[field] == UNMATCHED {
+                    self.augment(field);
+                }
+            }
+        }
+    }
+}
+
+// Fields and columns reachable from an unmatched field by alternating paths. Since the matching
+// is maximum, every column reached is matched, so the fields outnumber their columns by one.
+fn find_hall_violation(
+    field_to_columns: &[Vec<usize>],
+    matcher: &Matcher,
+    unmatched_field: usize,
+) -> (Vec<usize>, Vec<usize>) {
+    let mut seen_fields = vec![false; field_to_columns.len()];
+    let mut seen_columns = vec![false; matcher.column_match.len()];
+    let mut queue = VecDeque::new();
+    seen_fields[unmatched_field] = true;
+    queue.push_back(unmatched_field);
+
+    while let Some(field) = queue.pop_front() {
+        for &column in field_to_columns[field].iter() {
+            if !seen_columns[column] {
+                seen_columns[column] = true;
+                let next_field = matcher.column_match[column];
+                if !seen_fields[next_field] {
+                    seen_fields[next_field] = true;
+                    queue.push_back(next_field);
+                }
+            }
+        }
+    }
+
+    let collect_indexes = |seen: Vec<bool>| {
+        seen.iter()
+            .enumerate()
+            .filter_map(|(index, seen)| if *seen { Some(index) } else { None })
+            .collect()
+    };
+    (collect_indexes(seen_fields), collect_indexes(seen_columns))
+}
+
+// Tarjan's strongly connected components, iteratively to cope with thousands of fields.
+fn find_strongly_connected_components(edges: &[Vec<usize>]) -> Vec<usize> {
+    let node_count = edges.len();
+    let mut index_of = vec![UNMATCHED; node_count];
+    let mut low_link = vec![0; node_count];
+    let mut on_stack = vec![false; node_count];
+    let mut stack = vec![];
+    let mut component_of = vec![UNMATCHED; node_count];
+    let mut next_index = 0;
+    let mut component_count = 0;
+
+    for root in 0..node_count {
+        if index_of[root] != UNMATCHED {
+            continue;
+        }
+
+        let mut call_stack: Vec<(usize, usize)> = vec![(root, 0)];
+        index_of[root] = next_index;
+        low_link[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some(&mut (node, ref mut edge_index)) = call_stack.last_mut() {
+            if let Some(&next) = edges[node].get(*edge_index) {
+                *edge_index += 1;
+                if index_of[next] == UNMATCHED {
+                    index_of[next] = next_index;
+                    low_link[next] = next_index;
+                    next_index += 1;
+                    stack.push(next);
+                    on_stack[next] = true;
+                    call_stack.push((next, 0));
+                } else if on_stack[next] {
+                    low_link[node] = low_link[node].min(index_of[next]);
+                }
+            } else {
+                call_stack.pop();
+                if let Some(&(parent, _)) = call_stack.last() {
+                    low_link[parent] = low_link[parent].min(low_link[node]);
+                }
+
+                if low_link[node] == index_of[node] {
+                    loop {
+                        let member = stack.pop().unwrap();
+                        on_stack[member] = false;
+                        component_of[member] = component_count;
+                        if member == node {
+                            break;
+                        }
+                    }
+                    component_count += 1;
+                }
+            }
+        }
+    }
+
+    component_of
+}
+
+// A field can move to another compatible column exactly when that column's field can in turn
+// move along, ending either back at the first field (a cycle) or at an unused column.
+fn find_alternatives(
+    field_to_columns: &[Vec<usize>],
+    matcher: &Matcher,
+) -> Vec<(usize, Vec<usize>)> {
+    let field_count = field_to_columns.len();
+
+    // field -> fields whose column it could take instead
+    let displaces: Vec<Vec<usize>> = field_to_columns
+        .iter()
+        .enumerate()
+        .map(|(field, columns)| {
+            columns
+                .iter()
+                .map(|&column| matcher.column_match[column])
+                .filter(|&other| other != UNMATCHED && other != field)
+                .collect()
+        })
+        .collect();
+    let component_of = find_strongly_connected_components(&displaces);
+
+    // fields that can reach an unused column through a chain of displacements
+    let mut reaches_free_column: Vec<bool> = field_to_columns
+        .iter()
+        .map(|columns| {
+            columns
+                .iter()
+                .any(|&c| matcher.column_match[c] == UNMATCHED)
+        })
+        .collect();
+    let mut displaced_by: Vec<Vec<usize>> = vec![vec![]; field_count];
+    for (field, others) in displaces.iter().enumerate() {
+        for &other in others {
+            displaced_by[other].push(field);
+        }
+    }
+    let mut queue: VecDeque<usize> = (0..field_count)
+        .filter(|f| reaches_free_column[*f])
+        .collect();
+    while let Some(field) = queue.pop_front() {
+        for &previous in displaced_by[field].iter() {
+            if !reaches_free_column[previous] {
+                reaches_free_column[previous] = true;
+                queue.push_back(previous);
+            }
+        }
+    }
+
+    let mut result = vec![];
+    for (field, columns) in field_to_columns.iter().enumerate() {
+        let alternative_columns: Vec<usize> = columns
+            .iter()
+            .copied()
+            .filter(|&column| column != matcher.field_match[field])
+            .filter(|&column| {
+                let other = matcher.column_match[column];
+                other == UNMATCHED
+                    || component_of[other] == component_of[field]
+                    || reaches_free_column[other]
+            })
+            .collect();
+
+        if !alternative_columns.is_empty() {
+            result.push((field, alternative_columns));
+        }
+    }
+
+    result
+}
+
+// field_to_columns lists the ticket columns each field's rules allow.
+pub fn solve_field_mapping(field_to_columns: &[Vec<usize>], column_count: usize) -> FieldMapping {
+    let mut matcher = Matcher::new(field_to_columns, column_count);
+    matcher.run();
+
+    if let Some(unmatched_field) = matcher.field_match.iter().position(|c| *c == UNMATCHED) {
+        let (fields, columns) = find_hall_violation(field_to_columns, &matcher, unmatched_field);
+        return FieldMapping::Impossible { fields, columns };
+    }
+
+    let alternatives = find_alternatives(field_to_columns, &matcher);
+    if alternatives.is_empty() {
+        FieldMapping::Unique(matcher.field_match)
+    } else {
+        FieldMapping::Ambiguous {
+            mapping: matcher.field_match,
+            alternatives,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{solve_field_mapping, FieldMapping};
+
+    #[test]
+    fn test_unique_mapping() {
+        // the part 2 sample: column 0 is row, column 1 is class, column 2 is seat
+        let field_to_columns = vec![vec![1, 2], vec![0, 1, 2], vec![2]];
+
+        assert_eq!(
+            FieldMapping::Unique(vec![1, 0, 2]),
+            solve_field_mapping(&field_to_columns, 3)
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_mapping() {
+        let field_to_columns = vec![vec![0, 1], vec![0, 1], vec![2]];
+
+        match solve_field_mapping(&field_to_columns, 3) {
+            FieldMapping::Ambiguous {
+                mapping,
+                alternatives,
+            } => {
+                assert_eq!(2, mapping[2]);
+                assert_eq!(
+                    vec![(0, vec![1 - mapping[0]]), (1, vec![1 - mapping[1]])],
+                    alternatives
+                );
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // a spare column makes the field that could use it ambiguous, too
+        match solve_field_mapping(&[vec![0], vec![1, 2]], 3) {
+            FieldMapping::Ambiguous { alternatives, .. } => assert_eq!(1, alternatives[0].0),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_impossible_mapping() {
+        let field_to_columns = vec![vec![0, 1], vec![1], vec![1], vec![0, 2, 3]];
+
+        assert_eq!(
+            FieldMapping::Impossible {
+                fields: vec![1, 2],
+                columns: vec![1],
+            },
+            solve_field_mapping(&field_to_columns, 4)
+        );
+    }
+}
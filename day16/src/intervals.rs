@@ -0,0 +1,192 @@
+use crate::rules::FieldRule;
+
+// Disjoint, sorted, non-adjacent inclusive ranges, for membership tests by binary search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges: Vec<(i64, i64)>,
+}
+
+impl IntervalSet {
+    pub fn new(ranges: impl IntoIterator<Item = (i64, i64)>) -> IntervalSet {
+        let mut sorted: Vec<(i64, i64)> = ranges.into_iter().collect();
+        sorted.sort_unstable();
+
+        let mut merged: Vec<(i64, i64)> = Vec::with_capacity(sorted.len());
+        for (lower, upper) in sorted {
+            match merged.last_mut() {
+                Some(last) if lower <= last.1.saturating_add(1) => last.1 = last.1.max(upper),
+                _ => merged.push((lower, upper)),
+            }
+        }
+
+        IntervalSet { ranges: merged }
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        // the first range that doesn't end before the value is the only one that can contain it
+        let index = self.ranges.partition_point(|&(_, upper)| upper < value);
+        index < self.ranges.len() && self.ranges[index].0 <= value
+    }
+}
+
+// A set of field indexes, one bit per field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSet {
+    words: Vec<u64>,
+}
+
+impl FieldSet {
+    pub fn empty(field_count: usize) -> FieldSet {
+        FieldSet {
+            words: vec![0; field_count.div_ceil(64)],
+        }
+    }
+
+    pub fn all(field_count: usize) -> FieldSet {
+        let mut result = FieldSet::empty(field_count);
+        for field in 0..field_count {
+            result.insert(field);
+        }
+        result
+    }
+
+    pub fn insert(&mut self, field: usize) {
+        self.words[field / 64] |= 1 << (field % 64);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    pub fn intersect_with(&mut self, other: &FieldSet) {
+        for (word, other_word) in self.words.iter_mut().zip(other.words.iter()) {
+            *word &= other_word;
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(index, &word)| {
+            (0..64)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| index * 64 + bit)
+        })
+    }
+}
+
+// Splits the number line at every rule boundary, so that all values within a segment
+// match exactly the same fields. Finding a value's fields is then one binary search.
+#[derive(Debug)]
+pub struct FieldIndex {
+    segment_starts: Vec<i64>,
+    segment_fields: Vec<FieldSet>, // the last segment runs to infinity and matches nothing
+}
+
+impl FieldIndex {
+    pub fn new(rules: &[FieldRule]) -> FieldIndex {
+        let mut segment_starts: Vec<i64> = rules
+            .iter()
+            .flat_map(|rule| rule.ranges.iter())
+            .flat_map(|&(lower, upper)| vec![lower, upper.saturating_add(1)])
+            .collect();
+        segment_starts.sort_unstable();
+        segment_starts.dedup();
+
+        let mut segment_fields = vec![FieldSet::empty(rules.len()); segment_starts.len()];
+        for (field, rule) in rules.iter().enumerate() {
+            for &(lower, upper) in rule.ranges.iter() {
+                let first = segment_starts.binary_search(&lower).unwrap();
+                let end = segment_starts
+                    .binary_search(&upper.saturating_add(1))
+                    .unwrap();
+                for fields in segment_fields[first..end].iter_mut() {
+                    fields.insert(field);
+                }
+            }
+        }
+
+        FieldIndex {
+            segment_starts,
+            segment_fields,
+        }
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.segment_starts.len()
+    }
+
+    // The segment containing the value, or None if no field allows it: it's below or above
+    // every rule, or in a gap between them.
+    pub fn find_segment(&self, value: i64) -> Option<usize> {
+        let index = self.segment_starts.partition_point(|&start| start <= value);
+        index
+            .checked_sub(1)
+            .filter(|&segment| !self.segment_fields[segment].is_empty())
+    }
+
+    pub fn segment_fields(&self, segment: usize) -> &FieldSet {
+        &self.segment_fields[segment]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use numtheory::Rng;
+
+    use super::{FieldIndex, FieldSet, IntervalSet};
+    use crate::rules::FieldRule;
+
+    #[test]
+    fn test_interval_set_merges_ranges() {
+        let set = IntervalSet::new(vec![(5, 7), (1, 3), (4, 4), (10, 12), (11, 11), (20, 20)]);
+        assert_eq!(vec![(1, 7), (10, 12), (20, 20)], set.ranges);
+
+        let members: Vec<i64> = (0..=22).filter(|&value| set.contains(value)).collect();
+        assert_eq!(vec![1, 2, 3, 4, 5, 6, 7, 10, 11, 12, 20], members);
+        assert!(!IntervalSet::new(vec![]).contains(0));
+    }
+
+    #[test]
+    fn test_field_set() {
+        let mut set = FieldSet::all(130);
+        let mut other = FieldSet::empty(130);
+        for field in [0, 63, 64, 129].iter() {
+            other.insert(*field);
+        }
+        set.intersect_with(&other);
+
+        assert_eq!(vec![0, 63, 64, 129], set.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_field_index_matches_rules() {
+        let mut rng = Rng::new(16);
+        let lines: Vec<String> = (0..100)
+            .map(|field| {
+                let ranges: Vec<String> = (0..1 + rng.below(4))
+                    .map(|_| {
+                        let lower = rng.below(1000);
+                        format!("{}-{}", lower, lower + rng.below(50))
+                    })
+                    .collect();
+                format!("field {}: {}", field, ranges.join(" or "))
+            })
+            .collect();
+        let rules: Vec<FieldRule> = lines.iter().map(|line| FieldRule::parse(line)).collect();
+        let index = FieldIndex::new(&rules);
+        let all_ranges = IntervalSet::new(rules.iter().flat_map(|rule| rule.ranges.clone()));
+
+        for value in -5..1100 {
+            let expected: Vec<usize> = (0..rules.len())
+                .filter(|&field| rules[field].distance(value) == 0)
+                .collect();
+            let actual: Vec<usize> = match index.find_segment(value) {
+                Some(segment) => index.segment_fields(segment).iter().collect(),
+                None => vec![],
+            };
+            assert_eq!(expected.is_empty(), index.find_segment(value).is_none());
+
+            assert_eq!(expected, actual, "value {}", value);
+            assert_eq!(!expected.is_empty(), all_ranges.contains(value));
+        }
+    }
+}
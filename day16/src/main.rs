@@ -1,171 +1,297 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fs,
-};
+use std::{env, fs};
 
-fn main() {
-    let contents = fs::read_to_string(
-        "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day16/input.txt",
-    )
-    .unwrap();
+mod export;
+mod field_mapping;
+mod intervals;
+mod rules;
+mod validation;
+
+use export::decode_tickets;
+use field_mapping::{solve_field_mapping, FieldMapping};
+use intervals::{FieldIndex, FieldSet};
+use rules::{FieldFilter, FieldRule};
+use validation::validate_tickets;
+
+#[derive(Debug)]
+struct Notes<'a> {
+    rules: Vec<FieldRule<'a>>,
+    your_ticket: Vec<i64>,
+    nearby_tickets: Vec<Vec<i64>>,
+}
 
+fn parse_ticket(line: &str) -> Vec<i64> {
+    line.trim().split(',').map(|x| x.parse().unwrap()).collect()
+}
+
+fn parse_notes(contents: &str) -> Notes<'_> {
     let groups: Vec<_> = contents.trim().split("\n\n").collect();
     let fields_info = groups[0];
     let your_ticket_info = groups[1];
     let nearby_tickets_info = groups[2];
 
-    let fields: Vec<_> = fields_info.trim().split("\n").collect();
-    let field_valid_ranges: Vec<_> = fields
-        .iter()
-        .map(|&x| x.split(": ").last().unwrap())
-        .flat_map(|x| x.split(" or "))
-        .collect();
-    let field_valid_rules: Vec<(i64, i64)> = field_valid_ranges
-        .iter()
-        .map(|&x| x.split("-").map(|y| y.parse().unwrap()))
-        .map(|mut y| (y.next().unwrap(), y.last().unwrap()))
-        .collect();
+    let rules: Vec<_> = fields_info.trim().lines().map(FieldRule::parse).collect();
 
-    let your_ticket_data: Vec<_> = your_ticket_info.trim().split("\n").collect();
+    let your_ticket_data: Vec<_> = your_ticket_info.trim().lines().collect();
     assert!(your_ticket_data[0] == "your ticket:");
-    let your_ticket_numbers: Vec<i64> = your_ticket_data[1]
-        .trim()
-        .split(",")
-        .map(|x| x.parse().unwrap())
-        .collect();
+    let your_ticket = parse_ticket(your_ticket_data[1]);
 
-    let nearby_tickets_data: Vec<_> = nearby_tickets_info.trim().split("\n").collect();
+    let nearby_tickets_data: Vec<_> = nearby_tickets_info.trim().lines().collect();
     assert!(nearby_tickets_data[0] == "nearby tickets:");
     let nearby_tickets: Vec<Vec<i64>> = nearby_tickets_data[1..nearby_tickets_data.len()]
         .iter()
-        .map(|&ticket| {
-            ticket
-                .trim()
-                .split(",")
-                .map(|y| y.parse().unwrap())
-                .collect()
-        })
+        .map(|&ticket| parse_ticket(ticket))
         .collect();
 
-    let (part1_soln, mut valid_tickets) = solve_part1(&field_valid_rules, &nearby_tickets);
-    println!("{}", part1_soln);
+    Notes {
+        rules,
+        your_ticket,
+        nearby_tickets,
+    }
+}
+
+#[derive(Debug)]
+struct Options {
+    input_path: String,
+    report: bool,
+    field_filter: FieldFilter,
+    json_path: Option<String>,
+    csv_path: Option<String>,
+}
 
-    valid_tickets.push(your_ticket_numbers.clone());
+fn parse_options(args: impl Iterator<Item = String>) -> Options {
+    let mut options = Options {
+        input_path: String::from(
+            "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day16/input.txt",
+        ),
+        report: false,
+        field_filter: FieldFilter::parse("departure*"),
+        json_path: None,
+        csv_path: None,
+    };
+
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--report" => options.report = true,
+            "--fields" => {
+                options.field_filter =
+                    FieldFilter::parse(&args.next().expect("missing field name patterns"))
+            }
+            "--json" => options.json_path = Some(args.next().expect("missing JSON path")),
+            "--csv" => options.csv_path = Some(args.next().expect("missing CSV path")),
+            _ if arg.starts_with("--") => panic!("unrecognized option: {}", arg),
+            _ => options.input_path = arg,
+        }
+    }
+
+    options
+}
+
+fn main() {
+    let options = parse_options(env::args().skip(1));
+    let contents = fs::read_to_string(&options.input_path).unwrap();
+    let notes = parse_notes(&contents);
+
+    let report = validate_tickets(&notes.rules, &notes.nearby_tickets);
+    println!("{}", report.error_rate());
+    if options.report {
+        println!("{}", report.describe(&notes.rules, &notes.nearby_tickets));
+    }
+
+    let mut valid_tickets = report.valid_tickets.clone();
+    valid_tickets.push(notes.your_ticket.clone());
+    let mapping = match find_field_mapping(&notes.rules, &valid_tickets, notes.your_ticket.len()) {
+        Ok(mapping) => mapping,
+        Err(message) => {
+            println!("{}", message);
+            return;
+        }
+    };
     println!(
         "{}",
-        solve_part2(&fields, &valid_tickets, &your_ticket_numbers)
+        solve_part2(
+            &notes.rules,
+            &mapping,
+            &notes.your_ticket,
+            &options.field_filter
+        )
     );
+
+    if options.json_path.is_some() || options.csv_path.is_some() {
+        let decoded = decode_tickets(
+            &notes.rules,
+            &mapping,
+            &notes.your_ticket,
+            &report.valid_tickets,
+        );
+        if let Some(json_path) = &options.json_path {
+            fs::write(json_path, decoded.to_json()).unwrap();
+        }
+        if let Some(csv_path) = &options.csv_path {
+            fs::write(csv_path, decoded.to_csv()).unwrap();
+        }
+    }
 }
 
-fn solve_part1(
-    field_valid_rules: &Vec<(i64, i64)>,
-    nearby_tickets: &Vec<Vec<i64>>,
-) -> (i64, Vec<Vec<i64>>) {
-    let mut result: i64 = 0;
-    let mut valid_tickets: Vec<Vec<i64>> = Vec::new();
-
-    for nearby_ticket in nearby_tickets {
-        let mut is_valid_ticket = true;
-        for value in nearby_ticket {
-            let is_valid_value: bool = field_valid_rules
-                .iter()
-                .filter(|(lower, upper)| lower <= value && upper >= value)
-                .next()
-                .is_some();
-
-            if !is_valid_value {
-                is_valid_ticket = false;
-                result += value;
+// Describes why the fields can't be matched to ticket columns one-to-one.
+fn describe_mapping_problem(field_names: &[&str], mapping: &FieldMapping) -> String {
+    match mapping {
+        FieldMapping::Unique(_) => String::from("the field mapping is unique"),
+        FieldMapping::Ambiguous {
+            mapping,
+            alternatives,
+        } => {
+            let mut lines = vec![format!(
+                "ambiguous: {} fields could be in more than one column",
+                alternatives.len()
+            )];
+            for (field_index, columns) in alternatives {
+                lines.push(format!(
+                    "  {} is in column {} or {:?}",
+                    field_names[*field_index], mapping[*field_index], columns
+                ));
             }
+            lines.join("\n")
         }
-
-        if is_valid_ticket {
-            valid_tickets.push(nearby_ticket.clone());
+        FieldMapping::Impossible { fields, columns } => {
+            let names: Vec<_> = fields.iter().map(|&index| field_names[index]).collect();
+            format!(
+                "impossible: the {} fields {} only fit in the {} columns {:?}",
+                fields.len(),
+                names.join(", "),
+                columns.len(),
+                columns
+            )
         }
     }
-
-    (result, valid_tickets)
 }
 
-fn solve_part2(fields: &Vec<&str>, valid_tickets: &Vec<Vec<i64>>, your_ticket: &Vec<i64>) -> i64 {
-    let field_names: Vec<_> = fields
-        .iter()
-        .map(|&x| x.split(": ").next().unwrap())
-        .collect();
-    let field_rule_elements: Vec<Vec<&str>> = fields
-        .iter()
-        .map(|&x| x.split(": ").last().unwrap().split(" or ").collect())
-        .collect();
-    let field_rule_ranges: Vec<Vec<(i64, i64)>> = field_rule_elements
-        .iter()
-        .map(|ranges| {
-            ranges
-                .iter()
-                .map(|&range| range.split("-").map(|value| value.parse::<i64>().unwrap()))
-                .map(|mut iter| (iter.next().unwrap(), iter.next().unwrap()))
-                .collect()
-        })
-        .collect();
+// Returns the ticket column of each field.
+fn find_field_mapping(
+    rules: &[FieldRule],
+    valid_tickets: &[Vec<i64>],
+    column_count: usize,
+) -> Result<Vec<usize>, String> {
+    let field_names: Vec<_> = rules.iter().map(|rule| rule.name).collect();
 
     // Figure out all the possible field indexes for each value on our ticket.
-    // We start off by having all field indexes be possible for each value, and then validate
-    // each ticket's values against all fields, discarding field indexes whose rules don't match.
-    let mut possible_ticket_index_to_field_index: Vec<HashSet<usize>> = Vec::new();
-    for _ in 0..your_ticket.len() {
-        possible_ticket_index_to_field_index.push((0..fields.len()).collect());
-    }
+    // We start off by having all field indexes be possible for each value, and then intersect
+    // with the fields allowing each ticket's value. Values in the same segment of the index
+    // allow the same fields, so each column only needs one intersection per distinct segment.
+    let field_index = FieldIndex::new(rules);
+    let mut field_index_to_columns: Vec<Vec<usize>> = vec![vec![]; rules.len()];
+    let mut seen_segments = vec![usize::MAX; field_index.segment_count()];
+    for ticket_index in 0..column_count {
+        let mut possible_field_indexes = FieldSet::all(rules.len());
+        for ticket in valid_tickets {
+            // nearby tickets were validated, but your own ticket wasn't
+            let value = ticket[ticket_index];
+            let segment = field_index.find_segment(value).ok_or_else(|| {
+                format!(
+                    "column {} has the value {}, which no field allows",
+                    ticket_index, value
+                )
+            })?;
+            if seen_segments[segment] != ticket_index {
+                seen_segments[segment] = ticket_index;
+                possible_field_indexes.intersect_with(field_index.segment_fields(segment));
+            }
+        }
 
-    for ticket in valid_tickets {
-        for (ticket_index, ticket_value) in ticket.iter().enumerate() {
-            let valid_mappings: HashSet<_> = field_rule_ranges
-                .iter()
-                .enumerate()
-                .filter(|(_, rule)| {
-                    rule.iter()
-                        .filter(|(lower, upper)| lower <= ticket_value && upper >= ticket_value)
-                        .next()
-                        .is_some()
-                })
-                .map(|(rule_index, _)| rule_index)
-                .collect();
-
-            let prior_options = &possible_ticket_index_to_field_index[ticket_index];
-
-            let subsequent_options = prior_options
-                .intersection(&valid_mappings)
-                .copied()
-                .collect();
-            possible_ticket_index_to_field_index[ticket_index] = subsequent_options;
+        for field_index in possible_field_indexes.iter() {
+            field_index_to_columns[field_index].push(ticket_index);
         }
     }
 
-    // By this point, we should have enough options ruled out that we can repeatedly make
-    // forced choices ("only one option remaining" situations), then eliminate more options
-    // as a result, and repeat until we've matched every field index with its spot on the ticket.
-    let mut field_index_to_ticket_index: HashMap<usize, usize> = HashMap::new();
-    while field_index_to_ticket_index.len() < fields.len() {
-        for (ticket_index, possible_field_indexes) in
-            possible_ticket_index_to_field_index.iter().enumerate()
-        {
-            let remaining_field_indexes: HashSet<_> = possible_field_indexes
-                .difference(&field_index_to_ticket_index.keys().copied().collect())
-                .copied()
-                .collect();
-            if remaining_field_indexes.len() == 1 {
-                field_index_to_ticket_index.insert(
-                    remaining_field_indexes.iter().next().unwrap().clone(),
-                    ticket_index,
-                );
-            }
-        }
+    // Each field must go in a distinct column that all its values fit. Repeatedly making
+    // forced choices isn't guaranteed to finish, so find a maximum matching instead,
+    // which also tells us if the answer is ambiguous or impossible.
+    match solve_field_mapping(&field_index_to_columns, column_count) {
+        FieldMapping::Unique(mapping) => Ok(mapping),
+        problem => Err(describe_mapping_problem(&field_names, &problem)),
     }
+}
 
+// Multiplies together your ticket's values for the fields the filter selects.
+fn solve_part2(
+    rules: &[FieldRule],
+    field_index_to_ticket_index: &[usize],
+    your_ticket: &[i64],
+    field_filter: &FieldFilter,
+) -> i64 {
     let mut result: i64 = 1;
-    for (field_index, field_name) in field_names.iter().enumerate() {
-        if field_name.starts_with("departure") {
-            let ticket_index = field_index_to_ticket_index[&field_index];
+    for (field_index, rule) in rules.iter().enumerate() {
+        if field_filter.matches(rule.name) {
+            let ticket_index = field_index_to_ticket_index[field_index];
             result *= your_ticket[ticket_index];
         }
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{find_field_mapping, parse_notes, solve_part2};
+    use crate::rules::FieldFilter;
+
+    const PART2_SAMPLE: &str = "class: 0-1 or 4-19
+row: 0-5 or 8-19
+seat: 0-13 or 16-19
+
+your ticket:
+11,12,13
+
+nearby tickets:
+3,9,18
+15,1,5
+5,14,9
+";
+
+    #[test]
+    fn test_your_ticket_value_outside_every_rule() {
+        let notes = parse_notes(PART2_SAMPLE);
+        let mut tickets = notes.nearby_tickets.clone();
+        tickets.push(notes.your_ticket.clone());
+        assert_eq!(
+            Ok(vec![1, 0, 2]),
+            find_field_mapping(&notes.rules, &tickets, 3)
+        );
+
+        tickets.push(vec![11, -5, 13]);
+        assert_eq!(
+            Err(String::from(
+                "column 1 has the value -5, which no field allows"
+            )),
+            find_field_mapping(&notes.rules, &tickets, 3)
+        );
+
+        tickets.pop();
+        tickets.push(vec![11, 12, 25]);
+        assert_eq!(
+            Err(String::from(
+                "column 2 has the value 25, which no field allows"
+            )),
+            find_field_mapping(&notes.rules, &tickets, 3)
+        );
+    }
+
+    #[test]
+    fn test_field_filter_selects_multiplied_fields() {
+        let notes = parse_notes(PART2_SAMPLE);
+        let mapping = vec![1, 0, 2]; // your ticket has class 12, row 11 and seat 13
+        let product = |filter: &str| {
+            solve_part2(
+                &notes.rules,
+                &mapping,
+                &notes.your_ticket,
+                &FieldFilter::parse(filter),
+            )
+        };
+
+        assert_eq!(12, product("class"));
+        assert_eq!(12 * 13, product("class,seat"));
+        assert_eq!(11 * 13, product("s*,row"));
+        assert_eq!(12 * 11 * 13, product("*"));
+        assert_eq!(1, product("departure*"));
+    }
+}
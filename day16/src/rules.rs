@@ -0,0 +1,148 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldRule<'a> {
+    pub name: &'a str,
+    pub ranges: Vec<(i64, i64)>, // inclusive, a single value is a range of one
+}
+
+impl<'a> FieldRule<'a> {
+    // Parses lines like "class: 1-3 or 5 or 7-11".
+    pub fn parse(line: &'a str) -> FieldRule<'a> {
+        let (name, ranges) = line
+            .split_once(": ")
+            .unwrap_or_else(|| panic!("missing field name: {}", line));
+
+        let ranges = ranges
+            .split(" or ")
+            .map(|range| {
+                let mut bounds = range.trim().splitn(2, '-').map(|bound| {
+                    bound
+                        .trim()
+                        .parse::<i64>()
+                        .unwrap_or_else(|_| panic!("invalid range \"{}\" in: {}", range, line))
+                });
+                let lower = bounds.next().unwrap();
+                let upper = bounds.next().unwrap_or(lower);
+                assert!(lower <= upper, "empty range \"{}\" in: {}", range, line);
+                (lower, upper)
+            })
+            .collect();
+
+        FieldRule {
+            name: name.trim(),
+            ranges,
+        }
+    }
+
+    // How far the value is from the closest value the rule allows, zero if it matches.
+    pub fn distance(&self, value: i64) -> i64 {
+        self.ranges
+            .iter()
+            .map(|&(lower, upper)| {
+                if value < lower {
+                    lower - value
+                } else if value > upper {
+                    value - upper
+                } else {
+                    0
+                }
+            })
+            .min()
+            .unwrap()
+    }
+}
+
+impl<'a> fmt::Display for FieldRule<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ranges: Vec<String> = self
+            .ranges
+            .iter()
+            .map(|&(lower, upper)| {
+                if lower == upper {
+                    lower.to_string()
+                } else {
+                    format!("{}-{}", lower, upper)
+                }
+            })
+            .collect();
+        write!(f, "{}: {}", self.name, ranges.join(" or "))
+    }
+}
+
+// Selects fields by name from a comma-separated list of patterns. A pattern ending in "*"
+// matches every name starting with the rest of it, any other pattern only its exact name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldFilter {
+    patterns: Vec<String>,
+}
+
+impl FieldFilter {
+    pub fn parse(patterns: &str) -> FieldFilter {
+        let patterns: Vec<String> = patterns
+            .split(',')
+            .map(|pattern| pattern.trim().to_string())
+            .collect();
+        assert!(
+            patterns.iter().all(|pattern| !pattern.is_empty()),
+            "empty field name pattern in: {}",
+            patterns.join(",")
+        );
+        FieldFilter { patterns }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == pattern,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FieldFilter, FieldRule};
+
+    #[test]
+    fn test_parse_and_display() {
+        let rule = FieldRule::parse("departure location: 1-3 or 5 or 7-11");
+        assert_eq!("departure location", rule.name);
+        assert_eq!(vec![(1, 3), (5, 5), (7, 11)], rule.ranges);
+        assert_eq!("departure location: 1-3 or 5 or 7-11", rule.to_string());
+
+        let sample_rules: Vec<_> = include_str!("../sample_input.txt")
+            .split("\n\n")
+            .next()
+            .unwrap()
+            .lines()
+            .map(FieldRule::parse)
+            .collect();
+        assert_eq!(3, sample_rules.len());
+        assert_eq!(vec![(13, 40), (45, 50)], sample_rules[2].ranges);
+    }
+
+    #[test]
+    fn test_distance() {
+        let rule = FieldRule::parse("class: 1-3 or 5 or 10-11");
+
+        for &(value, distance) in [(0, 1), (2, 0), (4, 1), (5, 0), (7, 2), (8, 2), (14, 3)].iter() {
+            assert_eq!(distance, rule.distance(value), "value {}", value);
+        }
+    }
+
+    #[test]
+    fn test_field_filter() {
+        let departures = FieldFilter::parse("departure*");
+        assert!(departures.matches("departure location"));
+        assert!(departures.matches("departure"));
+        assert!(!departures.matches("arrival location"));
+
+        let exact = FieldFilter::parse("class, departure");
+        assert!(exact.matches("class"));
+        assert!(exact.matches("departure"));
+        assert!(!exact.matches("departure location"));
+        assert!(!exact.matches("classroom"));
+    }
+}
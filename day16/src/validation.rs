@@ -0,0 +1,172 @@
+use crate::{intervals::IntervalSet, rules::FieldRule};
+
+// A ticket value that no rule allows, and the rules that came closest to allowing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidValue {
+    pub column: usize,
+    pub value: i64,
+    pub distance: i64,
+    pub closest_rules: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidTicket {
+    pub ticket_index: usize, // position among the nearby tickets
+    pub values: Vec<InvalidValue>,
+}
+
+#[derive(Debug)]
+pub struct ValidationReport {
+    pub valid_tickets: Vec<Vec<i64>>,
+    pub invalid_tickets: Vec<InvalidTicket>,
+}
+
+impl ValidationReport {
+    // The puzzle's "ticket scanning error rate": the sum of all invalid values.
+    pub fn error_rate(&self) -> i64 {
+        self.invalid_tickets
+            .iter()
+            .flat_map(|ticket| ticket.values.iter())
+            .map(|invalid| invalid.value)
+            .sum()
+    }
+
+    pub fn describe(&self, rules: &[FieldRule], nearby_tickets: &[Vec<i64>]) -> String {
+        let mut lines = vec![format!(
+            "{} of {} nearby tickets are invalid",
+            self.invalid_tickets.len(),
+            nearby_tickets.len()
+        )];
+
+        for ticket in self.invalid_tickets.iter() {
+            let values: Vec<String> = nearby_tickets[ticket.ticket_index]
+                .iter()
+                .map(|value| value.to_string())
+                .collect();
+            lines.push(format!(
+                "nearby ticket {} ({}):",
+                ticket.ticket_index + 1,
+                values.join(",")
+            ));
+
+            for invalid in ticket.values.iter() {
+                let closest: Vec<String> = invalid
+                    .closest_rules
+                    .iter()
+                    .map(|&rule_index| format!("\"{}\"", rules[rule_index]))
+                    .collect();
+                lines.push(format!(
+                    "  value {} in column {} is {} away from {}",
+                    invalid.value,
+                    invalid.column,
+                    invalid.distance,
+                    closest.join(", ")
+                ));
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+fn make_invalid_value(rules: &[FieldRule], column: usize, value: i64) -> InvalidValue {
+    let distances: Vec<i64> = rules.iter().map(|rule| rule.distance(value)).collect();
+    let distance = distances.iter().copied().min().unwrap();
+    let closest_rules = (0..rules.len())
+        .filter(|&rule_index| distances[rule_index] == distance)
+        .collect();
+
+    InvalidValue {
+        column,
+        value,
+        distance,
+        closest_rules,
+    }
+}
+
+pub fn validate_tickets(rules: &[FieldRule], nearby_tickets: &[Vec<i64>]) -> ValidationReport {
+    // A value is valid if any rule allows it, so only the union of all ranges matters.
+    let allowed_values = IntervalSet::new(rules.iter().flat_map(|rule| rule.ranges.clone()));
+    let mut valid_tickets = vec![];
+    let mut invalid_tickets = vec![];
+
+    for (ticket_index, ticket) in nearby_tickets.iter().enumerate() {
+        let values: Vec<InvalidValue> = ticket
+            .iter()
+            .enumerate()
+            .filter(|(_, &value)| !allowed_values.contains(value))
+            .map(|(column, &value)| make_invalid_value(rules, column, value))
+            .collect();
+
+        if values.is_empty() {
+            valid_tickets.push(ticket.clone());
+        } else {
+            invalid_tickets.push(InvalidTicket {
+                ticket_index,
+                values,
+            });
+        }
+    }
+
+    ValidationReport {
+        valid_tickets,
+        invalid_tickets,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_tickets, InvalidValue};
+    use crate::{parse_notes, rules::FieldRule};
+
+    #[test]
+    fn test_sample_report() {
+        let notes = parse_notes(include_str!("../sample_input.txt"));
+        let report = validate_tickets(&notes.rules, &notes.nearby_tickets);
+
+        assert_eq!(71, report.error_rate());
+        assert_eq!(vec![vec![7, 3, 47]], report.valid_tickets);
+
+        let invalid_indexes: Vec<_> = report
+            .invalid_tickets
+            .iter()
+            .map(|ticket| ticket.ticket_index)
+            .collect();
+        assert_eq!(vec![1, 2, 3], invalid_indexes);
+        assert_eq!(
+            vec![InvalidValue {
+                column: 0,
+                value: 55,
+                distance: 5,
+                closest_rules: vec![2],
+            }],
+            report.invalid_tickets[1].values
+        );
+
+        let description = report.describe(&notes.rules, &notes.nearby_tickets);
+        assert!(description.starts_with("3 of 4 nearby tickets are invalid\n"));
+        assert!(description.contains(
+            "nearby ticket 4 (38,6,12):\n  value 12 in column 2 is 1 away from \"row: 6-11 or 33-44\", \"seat: 13-40 or 45-50\""
+        ));
+    }
+
+    #[test]
+    fn test_single_value_rules() {
+        let rules = vec![FieldRule::parse("a: 1 or 3"), FieldRule::parse("b: 10")];
+        let report = validate_tickets(&rules, &[vec![3, 10], vec![2, 7]]);
+
+        assert_eq!(vec![vec![3, 10]], report.valid_tickets);
+        let values: Vec<_> = report.invalid_tickets[0]
+            .values
+            .iter()
+            .map(|invalid| {
+                (
+                    invalid.value,
+                    invalid.distance,
+                    invalid.closest_rules.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(vec![(2, 1, vec![0]), (7, 3, vec![1])], values);
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+
+use crate::{int_sqrt, orientation::Orientation, Edge, Tile, TileVariantKey};
+
+#[derive(Debug)]
+struct PackedTile<'a> {
+    tile: &'a Tile,
+    edges: [[u64; 4]; 8], // per orientation, in Edge::variants() order
+}
+
+fn edge_cells(dimension: usize, edge: Edge) -> impl Iterator<Item = (usize, usize)> {
+    let max_coord = dimension - 1;
+    (0..dimension).map(move |i| match edge {
+        Edge::Top => (0, i),
+        Edge::Bottom => (max_coord, i),
+        Edge::Left => (i, 0),
+        Edge::Right => (i, max_coord),
+    })
+}
+
+// Edges are read in the same direction as TileVariant::get_edge(), first cell in the top bit.
+fn pack_edge(tile: &Tile, edge: Edge) -> u64 {
+    edge_cells(tile.image.len(), edge).fold(0, |acc, (x, y)| {
+        (acc << 1) | (tile.image[x][y] == '#') as u64
+    })
+}
+
+fn reverse_edge(edge: u64, dimension: usize) -> u64 {
+    edge.reverse_bits() >> (64 - dimension)
+}
+
+fn canonical_edge(edge: u64, dimension: usize) -> u64 {
+    edge.min(reverse_edge(edge, dimension))
+}
+
+impl<'a> PackedTile<'a> {
+    fn new(tile: &'a Tile) -> PackedTile<'a> {
+        let dimension = tile.image.len();
+        assert!(dimension <= 64);
+
+        let mut original_edges = [0u64; 4];
+        for (edge_index, edge) in Edge::variants().enumerate() {
+            original_edges[edge_index] = pack_edge(tile, edge);
+        }
+
+        let mut edges = [[0u64; 4]; 8];
+        for orientation in Orientation::all() {
+            edges[orientation.index()] =
+                orientation.apply_to_edges(&original_edges, |edge| reverse_edge(*edge, dimension));
+        }
+
+        PackedTile { tile, edges }
+    }
+
+    fn edge(&self, orientation: Orientation, edge: Edge) -> u64 {
+        let edge_index = Edge::variants().position(|e| e == edge).unwrap();
+        self.edges[orientation.index()][edge_index]
+    }
+}
+
+// Assembled once when it's created; the corners, tile map and images all come from that.
+#[derive(Debug)]
+pub struct FastAssembly<'a> {
+    tiles: Vec<PackedTile<'a>>,
+    edge_owners: HashMap<u64, Vec<usize>>, // canonical edge -> indexes of tiles that have it
+    dimension: usize,
+    corner_tiles: Vec<i64>,
+    layout: Vec<Vec<(usize, Orientation)>>, // tile indexes and orientations, row by row
+}
+
+impl<'a> FastAssembly<'a> {
+    pub fn new(tiles: &'a [Tile]) -> FastAssembly<'a> {
+        let dimension = tiles[0].image.len();
+        let tiles: Vec<PackedTile> = tiles.iter().map(PackedTile::new).collect();
+
+        let mut edge_owners: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (tile_index, tile) in tiles.iter().enumerate() {
+            for edge in tile.edges[0].iter() {
+                let owners = edge_owners
+                    .entry(canonical_edge(*edge, dimension))
+                    .or_default();
+                // a tile with the same edge twice, like an all-blank corner, owns it only once
+                if owners.last() != Some(&tile_index) {
+                    owners.push(tile_index);
+                }
+                assert!(
+                    owners.len() <= 2,
+                    "more than two tiles share the edge {:0width$b}",
+                    edge,
+                    width = dimension
+                );
+            }
+        }
+
+        let mut assembly = FastAssembly {
+            tiles,
+            edge_owners,
+            dimension,
+            corner_tiles: vec![],
+            layout: vec![],
+        };
+        assembly.corner_tiles = assembly.find_corner_tiles();
+        assembly.layout = assembly.assemble_indexes();
+        assembly
+    }
+
+    fn is_unmatched(&self, edge: u64) -> bool {
+        self.edge_owners[&canonical_edge(edge, self.dimension)].len() == 1
+    }
+
+    fn find_corner_tiles(&self) -> Vec<i64> {
+        let mut result: Vec<i64> = self
+            .tiles
+            .iter()
+            .filter(|tile| {
+                let unmatched = tile.edges[0]
+                    .iter()
+                    .filter(|edge| self.is_unmatched(**edge))
+                    .count();
+                assert!(unmatched <= 2);
+                unmatched == 2
+            })
+            .map(|tile| tile.tile.id)
+            .collect();
+
+        result.sort_unstable(); // for determinism
+        assert_eq!(4, result.len());
+
+        result
+    }
+
+    fn find_neighbor(
+        &self,
+        tile_index: usize,
+        edge_value: u64,
+        edge: Edge,
+    ) -> (usize, Orientation) {
+        let neighbor_index = *self.edge_owners[&canonical_edge(edge_value, self.dimension)]
+            .iter()
+            .find(|index| **index != tile_index)
+            .unwrap();
+        let neighbor = &self.tiles[neighbor_index];
+
+        let mut orientations = Orientation::all().filter(|o| neighbor.edge(*o, edge) == edge_value);
+        let orientation = orientations.next().unwrap();
+        assert!(orientations.next().is_none());
+
+        (neighbor_index, orientation)
+    }
+
+    fn assemble_indexes(&self) -> Vec<Vec<(usize, Orientation)>> {
+        let tile_map_side = int_sqrt(self.tiles.len());
+        let corner_id = self.corner_tiles[0];
+        let corner_index = self
+            .tiles
+            .iter()
+            .position(|tile| tile.tile.id == corner_id)
+            .unwrap();
+        let corner_orientation = Orientation::all()
+            .find(|o| {
+                let corner = &self.tiles[corner_index];
+                self.is_unmatched(corner.edge(*o, Edge::Top))
+                    && self.is_unmatched(corner.edge(*o, Edge::Left))
+            })
+            .unwrap();
+
+        let mut tile_map: Vec<Vec<(usize, Orientation)>> = Vec::with_capacity(tile_map_side);
+        for x in 0..tile_map_side {
+            let mut row: Vec<(usize, Orientation)> = Vec::with_capacity(tile_map_side);
+            for y in 0..tile_map_side {
+                let placed = if x == 0 && y == 0 {
+                    (corner_index, corner_orientation)
+                } else if x == 0 {
+                    let (left_index, left_orientation) = row[y - 1];
+                    let left_edge = self.tiles[left_index].edge(left_orientation, Edge::Right);
+                    self.find_neighbor(left_index, left_edge, Edge::Left)
+                } else {
+                    let (top_index, top_orientation) = tile_map[x - 1][y];
+                    let top_edge = self.tiles[top_index].edge(top_orientation, Edge::Bottom);
+                    self.find_neighbor(top_index, top_edge, Edge::Top)
+                };
+                row.push(placed);
+            }
+            tile_map.push(row);
+        }
+
+        tile_map
+    }
+
+    pub fn corner_tiles(&self) -> &[i64] {
+        &self.corner_tiles
+    }
+
+    pub fn assemble(&self) -> Vec<Vec<TileVariantKey>> {
+        self.layout
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|(index, orientation)| (self.tiles[*index].tile.id, *orientation))
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn make_combined_image(&self) -> Vec<Vec<char>> {
+        self.make_image(false)
+    }
+
+    pub fn make_bordered_image(&self) -> Vec<Vec<char>> {
+        self.make_image(true)
+    }
+
+    // Reads every cell straight from the original tiles through their orientations.
+    fn make_image(&self, with_borders: bool) -> Vec<Vec<char>> {
+        let cells = if with_borders {
+            0..self.dimension
+        } else {
+            1..(self.dimension - 1)
+        };
+
+        let mut full_image = Vec::with_capacity(self.layout.len() * cells.len());
+        for row in self.layout.iter() {
+            for img_x in cells.clone() {
+                let mut full_image_row = Vec::with_capacity(row.len() * cells.len());
+                for (index, orientation) in row.iter() {
+                    let image = &self.tiles[*index].tile.image;
+                    for img_y in cells.clone() {
+                        let (x, y) = orientation.source_coordinate(self.dimension, img_x, img_y);
+                        full_image_row.push(image[x][y]);
+                    }
+                }
+                full_image.push(full_image_row);
+            }
+        }
+
+        full_image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{canonical_edge, FastAssembly};
+    use crate::{
+        assemble_tile_map, get_all_variants_for_tile_set, make_bordered_image_from_tile_map,
+        make_combined_image_from_tile_map, parse_tile, Tile,
+    };
+
+    #[test]
+    fn test_canonical_edge() {
+        assert_eq!(0b0011, canonical_edge(0b1100, 4));
+        assert_eq!(0b0011, canonical_edge(0b0011, 4));
+        assert_eq!(0b0110, canonical_edge(0b0110, 4));
+    }
+
+    #[test]
+    fn test_same_assembly_as_original() {
+        let tiles: Vec<Tile> = include_str!("../sample_input.txt")
+            .trim()
+            .split("\n\n")
+            .map(parse_tile)
+            .collect();
+        let tile_variants = get_all_variants_for_tile_set(&tiles);
+        let tile_map = assemble_tile_map(&tiles, &tile_variants);
+
+        let assembly = FastAssembly::new(&tiles);
+        assert_eq!(vec![1171, 1951, 2971, 3079], assembly.corner_tiles());
+        assert_eq!(tile_map, assembly.assemble());
+        assert_eq!(
+            make_combined_image_from_tile_map(&tile_variants, &tile_map, 10),
+            assembly.make_combined_image()
+        );
+        assert_eq!(
+            make_bordered_image_from_tile_map(&tile_variants, &tile_map, 10),
+            assembly.make_bordered_image()
+        );
+    }
+
+    #[test]
+    fn test_tile_with_a_repeated_edge() {
+        // tile 1009 has a blank top and left edge, which are the same edge twice
+        let tiles: Vec<Tile> = "Tile 1009:
+.....
+.##.#
+.....
+.#.##
+...##
+
+Tile 2003:
+..##.
+#...#
+.##..
+###..
+#.##.
+
+Tile 3001:
+...##
+.###.
+###.#
+..#.#
+....#
+
+Tile 4007:
+#.##.
+..###
+####.
+#..#.
+#..##"
+            .split("\n\n")
+            .map(parse_tile)
+            .collect();
+        let tile_variants = get_all_variants_for_tile_set(&tiles);
+
+        let assembly = FastAssembly::new(&tiles);
+        assert_eq!(vec![1009, 2003, 3001, 4007], assembly.corner_tiles());
+        assert_eq!(
+            assemble_tile_map(&tiles, &tile_variants),
+            assembly.assemble()
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashSet;
+
+use numtheory::Rng;
+
+use crate::orientation::Orientation;
+
+#[derive(Debug, Clone, Copy)]
+pub struct GeneratorOptions {
+    pub tiles_per_side: usize,
+    pub tile_size: usize,
+    pub density_percent: usize, // share of '#' cells in a randomly generated image
+    pub seed: u64,
+}
+
+#[derive(Debug)]
+pub struct GeneratedPuzzle {
+    pub text: String,
+    pub expected_corner_product: i64,
+    pub image: Vec<Vec<char>>,
+}
+
+const MAX_EDGE_ATTEMPTS: usize = 1000;
+
+fn random_cells(rng: &mut Rng, length: usize, density_percent: usize) -> Vec<char> {
+    (0..length)
+        .map(|_| {
+            if rng.chance(density_percent as u64) {
+                '#'
+            } else {
+                '.'
+            }
+        })
+        .collect()
+}
+
+fn canonical_edge(edge: &[char]) -> Vec<char> {
+    let reversed: Vec<char> = edge.iter().rev().copied().collect();
+    edge.to_vec().min(reversed)
+}
+
+// Picks the inner cells of an edge between two fixed corners, such that the edge can only
+// match its counterpart on the neighboring tile: no other edge has the same cells
+// in either direction, and the edge isn't a palindrome.
+fn make_unique_edge(
+    rng: &mut Rng,
+    used_edges: &mut HashSet<Vec<char>>,
+    start_corner: char,
+    end_corner: char,
+    inner_length: usize,
+) -> Vec<char> {
+    for _ in 0..MAX_EDGE_ATTEMPTS {
+        let mut edge = vec![start_corner];
+        edge.extend(random_cells(rng, inner_length, 50));
+        edge.push(end_corner);
+
+        let reversed: Vec<char> = edge.iter().rev().copied().collect();
+        if edge != reversed && used_edges.insert(canonical_edge(&edge)) {
+            return edge;
+        }
+    }
+
+    panic!("could not find enough unique tile edges, use a larger tile size");
+}
+
+fn make_tile_ids(rng: &mut Rng, count: usize) -> Vec<i64> {
+    // four digits like the real puzzle when possible, wider ranges otherwise
+    let (low, high) = if count <= 9000 {
+        (1000, 9999)
+    } else {
+        (10000, 10000 + 10 * count as i64)
+    };
+
+    let mut seen = HashSet::new();
+    let mut result = Vec::with_capacity(count);
+    while result.len() < count {
+        let id = low + rng.below((high - low + 1) as u64) as i64;
+        if seen.insert(id) {
+            result.push(id);
+        }
+    }
+    result
+}
+
+pub fn generate_puzzle(
+    options: GeneratorOptions,
+    image: Option<Vec<Vec<char>>>,
+) -> GeneratedPuzzle {
+    let side = options.tiles_per_side;
+    let tile_size = options.tile_size;
+    let inner_size = tile_size - 2;
+    assert!(side >= 2);
+    assert!(tile_size >= 4);
+
+    let mut rng = Rng::new(options.seed);
+    let image = image.unwrap_or_else(|| {
+        (0..side * inner_size)
+            .map(|_| random_cells(&mut rng, side * inner_size, options.density_percent))
+            .collect()
+    });
+    assert_eq!(side * inner_size, image.len());
+    assert!(image.iter().all(|row| row.len() == image.len()));
+
+    // tile corners are shared by the horizontal and vertical edges that meet there
+    let corners: Vec<Vec<char>> = (0..=side)
+        .map(|_| random_cells(&mut rng, side + 1, 50))
+        .collect();
+    let mut used_edges = HashSet::new();
+    let horizontal_edges: Vec<Vec<Vec<char>>> = (0..=side)
+        .map(|x| {
+            (0..side)
+                .map(|y| {
+                    let (start, end) = (corners[x][y], corners[x][y + 1]);
+                    make_unique_edge(&mut rng, &mut used_edges, start, end, inner_size)
+                })
+                .collect()
+        })
+        .collect();
+    let vertical_edges: Vec<Vec<Vec<char>>> = (0..side)
+        .map(|x| {
+            (0..=side)
+                .map(|y| {
+                    let (start, end) = (corners[x][y], corners[x + 1][y]);
+                    make_unique_edge(&mut rng, &mut used_edges, start, end, inner_size)
+                })
+                .collect()
+        })
+        .collect();
+
+    let tile_ids = make_tile_ids(&mut rng, side * side);
+    let mut tiles: Vec<(i64, Vec<Vec<char>>)> = Vec::with_capacity(side * side);
+    for tile_x in 0..side {
+        for tile_y in 0..side {
+            let mut tile_image = vec![vec!['?'; tile_size]; tile_size];
+            tile_image[0] = horizontal_edges[tile_x][tile_y].clone();
+            tile_image[tile_size - 1] = horizontal_edges[tile_x + 1][tile_y].clone();
+            for img_x in 0..tile_size {
+                tile_image[img_x][0] = vertical_edges[tile_x][tile_y][img_x];
+                tile_image[img_x][tile_size - 1] = vertical_edges[tile_x][tile_y + 1][img_x];
+            }
+            for img_x in 1..(tile_size - 1) {
+                for img_y in 1..(tile_size - 1) {
+                    tile_image[img_x][img_y] =
+                        image[tile_x * inner_size + img_x - 1][tile_y * inner_size + img_y - 1];
+                }
+            }
+
+            let id = tile_ids[tile_x * side + tile_y];
+            let orientation = Orientation::from_index(rng.index(8));
+            tiles.push((id, orientation.apply(&tile_image)));
+        }
+    }
+
+    let expected_corner_product = [0, side - 1, side * (side - 1), side * side - 1]
+        .iter()
+        .try_fold(1i64, |acc, index| acc.checked_mul(tile_ids[*index]))
+        .expect("corner product overflows i64");
+
+    // Fisher-Yates shuffle
+    for index in (1..tiles.len()).rev() {
+        let other = rng.index(index + 1);
+        tiles.swap(index, other);
+    }
+
+    let text = tiles
+        .iter()
+        .map(|(id, tile_image)| {
+            let rows: Vec<String> = tile_image.iter().map(|row| row.iter().collect()).collect();
+            format!("Tile {}:\n{}", id, rows.join("\n"))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    GeneratedPuzzle {
+        text,
+        expected_corner_product,
+        image,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_puzzle, GeneratorOptions};
+    use crate::{
+        fast_assembly::FastAssembly, get_all_variants_for_tile_set, orientation::Orientation,
+        parse_tile, solve_part1, Tile,
+    };
+
+    #[test]
+    fn test_generated_puzzles_are_solved() {
+        for seed in 0..20 {
+            let options = GeneratorOptions {
+                tiles_per_side: 2 + (seed as usize % 5),
+                tile_size: 10,
+                density_percent: 30,
+                seed,
+            };
+            let puzzle = generate_puzzle(options, None);
+
+            let tiles: Vec<Tile> = puzzle.text.split("\n\n").map(parse_tile).collect();
+            let tile_variants = get_all_variants_for_tile_set(&tiles);
+            let assembly = FastAssembly::new(&tiles);
+
+            assert_eq!(
+                puzzle.expected_corner_product,
+                solve_part1(&tiles, &tile_variants, options.tile_size)
+            );
+            assert_eq!(
+                puzzle.expected_corner_product,
+                assembly.corner_tiles().iter().product::<i64>()
+            );
+            let combined_image = assembly.make_combined_image();
+            assert!(Orientation::all().any(|o| o.apply(&puzzle.image) == combined_image));
+        }
+    }
+
+    #[test]
+    fn test_provided_image_is_used() {
+        let image = vec![vec!['#'; 16]; 16];
+        let options = GeneratorOptions {
+            tiles_per_side: 2,
+            tile_size: 10,
+            density_percent: 0,
+            seed: 42,
+        };
+
+        let puzzle = generate_puzzle(options, Some(image.clone()));
+        assert_eq!(image, puzzle.image);
+        assert_eq!(4, puzzle.text.matches("Tile ").count());
+    }
+}
@@ -1,4 +1,19 @@
-use std::{collections::HashMap, fs};
+use std::{collections::HashMap, env, fs};
+
+use fast_assembly::FastAssembly;
+use generator::{generate_puzzle, GeneratorOptions};
+use orientation::Orientation;
+use patterns::{
+    calculate_roughness, find_overlapping_matches, find_pattern_matches, get_matched_cells,
+    parse_patterns, ExcludedPatterns, Pattern, PatternMatch,
+};
+use render::{render_image, RenderOptions, TileLayout};
+
+mod fast_assembly;
+mod generator;
+mod orientation;
+mod patterns;
+mod render;
 
 #[derive(Debug)]
 struct Tile {
@@ -21,10 +36,14 @@ fn parse_tile(tile_data: &str) -> Tile {
     Tile { id, image }
 }
 
+type TileVariantKey = (i64, Orientation);
+type TileVariantMap = HashMap<TileVariantKey, TileVariant>;
+type EdgeIndex = HashMap<Vec<char>, Vec<(TileVariantKey, Edge)>>;
+
 #[derive(Debug)]
 struct TileVariant {
     id: i64,
-    variant: usize,
+    orientation: Orientation,
     image: Vec<Vec<char>>,
 }
 
@@ -87,8 +106,8 @@ impl TileVariant {
     }
 }
 
-fn right_rotate_image(image: &Vec<Vec<char>>) -> Vec<Vec<char>> {
-    let mut result = image.clone();
+fn right_rotate_image(image: &[Vec<char>]) -> Vec<Vec<char>> {
+    let mut result = image.to_vec();
     let image_dimension = image.len();
     let max_image_coord = image_dimension - 1;
 
@@ -103,8 +122,8 @@ fn right_rotate_image(image: &Vec<Vec<char>>) -> Vec<Vec<char>> {
     result
 }
 
-fn horizontal_flip_image(image: &Vec<Vec<char>>) -> Vec<Vec<char>> {
-    let mut result = image.clone();
+fn horizontal_flip_image(image: &[Vec<char>]) -> Vec<Vec<char>> {
+    let mut result = image.to_vec();
     let image_dimension = image.len();
     let max_image_coord = image_dimension - 1;
 
@@ -127,17 +146,17 @@ fn make_all_tile_variants(tile: &Tile) -> Vec<TileVariant> {
     for rotation_id in 0..=3 {
         result.push(TileVariant {
             id: tile.id,
-            variant: rotation_id,
+            orientation: Orientation::new(false, rotation_id),
             image: rotated_image.clone(),
         });
         rotated_image = right_rotate_image(&rotated_image);
     }
 
-    let mut flipped_image = horizontal_flip_image(&original_image);
+    let mut flipped_image = horizontal_flip_image(original_image);
     for rotation_id in 0..=3 {
         result.push(TileVariant {
             id: tile.id,
-            variant: 4 + rotation_id,
+            orientation: Orientation::new(true, rotation_id),
             image: flipped_image.clone(),
         });
         flipped_image = right_rotate_image(&flipped_image);
@@ -146,17 +165,15 @@ fn make_all_tile_variants(tile: &Tile) -> Vec<TileVariant> {
     result
 }
 
-fn get_all_variants_for_tile_set(tiles: &Vec<Tile>) -> HashMap<(i64, usize), TileVariant> {
+fn get_all_variants_for_tile_set(tiles: &[Tile]) -> TileVariantMap {
     tiles
         .iter()
-        .flat_map(|x| make_all_tile_variants(x))
-        .map(|x| ((x.id, x.variant), x))
+        .flat_map(make_all_tile_variants)
+        .map(|x| ((x.id, x.orientation), x))
         .collect()
 }
 
-fn make_edge_index(
-    tile_variants: &HashMap<(i64, usize), TileVariant>,
-) -> HashMap<Vec<char>, Vec<((i64, usize), Edge)>> {
+fn make_edge_index(tile_variants: &TileVariantMap) -> EdgeIndex {
     let mut result = HashMap::new();
 
     for (key, variant) in tile_variants {
@@ -174,11 +191,11 @@ fn make_edge_index(
 }
 
 fn get_other_tile_matches_for_variant_edge(
-    edge_index: &HashMap<Vec<char>, Vec<((i64, usize), Edge)>>,
+    edge_index: &EdgeIndex,
     tile_id: i64,
-    edge: &Vec<char>,
+    edge: &[char],
     edge_direction: &Edge,
-) -> Vec<((i64, usize), Edge)> {
+) -> Vec<(TileVariantKey, Edge)> {
     let opposite_edge = edge_direction.opposite();
 
     edge_index[edge]
@@ -189,17 +206,17 @@ fn get_other_tile_matches_for_variant_edge(
 }
 
 fn find_corner_tiles(
-    tiles: &Vec<Tile>,
-    tile_variants: &HashMap<(i64, usize), TileVariant>,
-    edge_index: &HashMap<Vec<char>, Vec<((i64, usize), Edge)>>,
+    tiles: &[Tile],
+    tile_variants: &TileVariantMap,
+    edge_index: &EdgeIndex,
 ) -> Vec<i64> {
     let mut result = vec![];
 
     for tile in tiles {
         let mut best_matched_variant_unmatched_directions: Vec<Edge> = Edge::variants().collect();
 
-        for variant_id in 0..8usize {
-            let key = (tile.id, variant_id);
+        for orientation in Orientation::all() {
+            let key = (tile.id, orientation);
             let variant = &tile_variants[&key];
 
             let mut unmatched_edge_directions: Vec<Edge> = Vec::new();
@@ -241,30 +258,26 @@ fn find_corner_tiles(
     result
 }
 
-fn solve_part1(
-    tiles: &Vec<Tile>,
-    tile_variants: &HashMap<(i64, usize), TileVariant>,
-    _tile_size: usize,
-) -> i64 {
+fn solve_part1(tiles: &[Tile], tile_variants: &TileVariantMap, _tile_size: usize) -> i64 {
     let edge_index = make_edge_index(tile_variants);
 
     find_corner_tiles(tiles, tile_variants, &edge_index)
         .iter()
-        .fold(1, |acc, elem| acc * elem)
+        .product()
 }
 
 fn int_sqrt(value: usize) -> usize {
-    (0..=value).filter(|v| v * v == value).next().unwrap()
+    (0..=value).find(|v| v * v == value).unwrap()
 }
 
 fn add_top_left_corner_to_tile_map(
-    tile_variants: &HashMap<(i64, usize), TileVariant>,
-    edge_index: &HashMap<Vec<char>, Vec<((i64, usize), Edge)>>,
-    tile_map: &mut Vec<Vec<(i64, usize)>>,
+    tile_variants: &TileVariantMap,
+    edge_index: &EdgeIndex,
+    tile_map: &mut [Vec<TileVariantKey>],
     corner_id: i64,
 ) {
-    for variant_id in 0..8 {
-        let tile_variant_key = (corner_id, variant_id);
+    for orientation in Orientation::all() {
+        let tile_variant_key = (corner_id, orientation);
         let variant = &tile_variants[&tile_variant_key];
 
         let top_edge_matches = get_other_tile_matches_for_variant_edge(
@@ -289,11 +302,11 @@ fn add_top_left_corner_to_tile_map(
 }
 
 fn find_tile_variant_with_neighbor(
-    edge_index: &HashMap<Vec<char>, Vec<((i64, usize), Edge)>>,
-    neighbor_tile_variant_key: &(i64, usize),
+    edge_index: &EdgeIndex,
+    neighbor_tile_variant_key: &TileVariantKey,
     neighbor_edge_direction: &Edge,
-    neighbor_edge: &Vec<char>,
-) -> (i64, usize) {
+    neighbor_edge: &[char],
+) -> TileVariantKey {
     let result_tile_edge_direction = neighbor_edge_direction.opposite();
 
     let possible_results: Vec<_> = get_other_tile_matches_for_variant_edge(
@@ -316,28 +329,28 @@ fn find_tile_variant_with_neighbor(
     possible_results[0]
 }
 
-fn make_empty_tile_map(tiles: &Vec<Tile>) -> Vec<Vec<(i64, usize)>> {
+fn make_empty_tile_map(tiles: &[Tile]) -> Vec<Vec<TileVariantKey>> {
     let tile_map_side = int_sqrt(tiles.len());
-    let mut tile_map: Vec<Vec<(i64, usize)>> = Vec::new();
-    let mut tile_map_row: Vec<(i64, usize)> = Vec::new();
-    tile_map_row.resize(tile_map_side, (-1, 0));
+    let mut tile_map: Vec<Vec<TileVariantKey>> = Vec::new();
+    let mut tile_map_row: Vec<TileVariantKey> = Vec::new();
+    tile_map_row.resize(tile_map_side, (-1, Orientation::IDENTITY));
     tile_map.resize(tile_map_side, tile_map_row);
 
     tile_map
 }
 
 fn fill_tile_map(
-    tile_variants: &HashMap<(i64, usize), TileVariant>,
-    edge_index: &HashMap<Vec<char>, Vec<((i64, usize), Edge)>>,
-    mut tile_map: &mut Vec<Vec<(i64, usize)>>,
-    corner_tiles: &Vec<i64>,
+    tile_variants: &TileVariantMap,
+    edge_index: &EdgeIndex,
+    tile_map: &mut [Vec<TileVariantKey>],
+    corner_tiles: &[i64],
 ) {
     // fill in the top left corner
     add_top_left_corner_to_tile_map(
         tile_variants,
-        &edge_index,
-        &mut tile_map,
-        corner_tiles.first().unwrap().clone(),
+        edge_index,
+        tile_map,
+        *corner_tiles.first().unwrap(),
     );
 
     // fill in the top row of the tile map
@@ -346,7 +359,7 @@ fn fill_tile_map(
         let neighbor_edge_direction = Edge::Right;
         let neighbor_edge = &tile_variants[neighbor_tile_variant_key].get_right_edge();
         tile_map[0][y] = find_tile_variant_with_neighbor(
-            &edge_index,
+            edge_index,
             neighbor_tile_variant_key,
             &neighbor_edge_direction,
             neighbor_edge,
@@ -361,7 +374,7 @@ fn fill_tile_map(
             let neighbor_edge = &tile_variants[neighbor_tile_variant_key].get_bottom_edge();
 
             tile_map[x][y] = find_tile_variant_with_neighbor(
-                &edge_index,
+                edge_index,
                 neighbor_tile_variant_key,
                 &neighbor_edge_direction,
                 neighbor_edge,
@@ -370,9 +383,40 @@ fn fill_tile_map(
     }
 }
 
+fn assemble_tile_map(tiles: &[Tile], tile_variants: &TileVariantMap) -> Vec<Vec<TileVariantKey>> {
+    let edge_index = make_edge_index(tile_variants);
+    let mut tile_map = make_empty_tile_map(tiles);
+    let corner_tiles = find_corner_tiles(tiles, tile_variants, &edge_index);
+
+    fill_tile_map(tile_variants, &edge_index, &mut tile_map, &corner_tiles);
+
+    tile_map
+}
+
+fn make_bordered_image_from_tile_map(
+    tile_variants: &TileVariantMap,
+    tile_map: &[Vec<TileVariantKey>],
+    tile_size: usize,
+) -> Vec<Vec<char>> {
+    let mut full_image: Vec<Vec<char>> = Vec::new();
+
+    for tile_row in tile_map.iter() {
+        for img_x in 0..tile_size {
+            full_image.push(
+                tile_row
+                    .iter()
+                    .flat_map(|key| tile_variants[key].image[img_x].iter().copied())
+                    .collect(),
+            );
+        }
+    }
+
+    full_image
+}
+
 fn make_combined_image_from_tile_map(
-    tile_variants: &HashMap<(i64, usize), TileVariant>,
-    tile_map: &Vec<Vec<(i64, usize)>>,
+    tile_variants: &TileVariantMap,
+    tile_map: &[Vec<TileVariantKey>],
     tile_size: usize,
 ) -> Vec<Vec<char>> {
     let borderless_tile_size = tile_size - 2;
@@ -401,147 +445,354 @@ fn make_combined_image_from_tile_map(
         }
     }
 
-    for x in 0..full_image.len() {
-        for y in 0..full_image[0].len() {
-            assert_ne!(full_image[x][y], '?');
+    for row in full_image.iter() {
+        for cell in row.iter() {
+            assert_ne!(*cell, '?');
         }
     }
 
     full_image
 }
 
-fn get_monster_image_indexes() -> (usize, usize, Vec<(usize, usize)>) {
-    let monster = [
-        "                  # ",
-        "#    ##    ##    ###",
-        " #  #  #  #  #  #   ",
-    ];
+fn calculate_sea_roughness(full_image: &[Vec<char>]) -> usize {
+    // generated puzzles needn't contain any sea monsters, in which case every '#' counts
+    let patterns = [Pattern::sea_monster()];
+    let matches = find_pattern_matches(full_image, &patterns);
+    let excluded_pattern_indexes = ExcludedPatterns::All.pattern_indexes(&patterns);
 
-    let monster_map: Vec<Vec<char>> = monster.iter().map(|row| row.chars().collect()).collect();
+    calculate_roughness(full_image, &matches, &excluded_pattern_indexes)
+}
 
-    let monster_indexes = monster_map
-        .iter()
-        .enumerate()
-        .flat_map(|(row_index, row)| {
-            std::iter::repeat(row_index).zip(row.iter().enumerate().filter_map(|(col_index, c)| {
-                if *c == '#' {
-                    Some(col_index)
-                } else {
-                    None
-                }
-            }))
-        })
-        .collect();
+fn solve_part2(tiles: &[Tile], tile_variants: &TileVariantMap, tile_size: usize) -> usize {
+    let tile_map = assemble_tile_map(tiles, tile_variants);
+    let full_image = make_combined_image_from_tile_map(tile_variants, &tile_map, tile_size);
 
-    (monster_map.len(), monster_map[0].len(), monster_indexes)
-}
-
-fn find_sea_monster_data(
-    full_image: &Vec<Vec<char>>,
-    monster_x_dim: usize,
-    monster_y_dim: usize,
-    monster_indexes: &Vec<(usize, usize)>,
-) -> Option<usize> {
-    let mut monsters_found = 0usize;
-    for root_x in 0..(full_image.len() - monster_x_dim) {
-        for root_y in 0..(full_image[0].len() - monster_y_dim) {
-            let mut monster_found = true;
-            for (monster_x, monster_y) in monster_indexes.iter().cloned() {
-                let x = root_x + monster_x;
-                let y = root_y + monster_y;
-
-                if full_image[x][y] != '#' {
-                    monster_found = false;
-                    break;
-                }
-            }
+    calculate_sea_roughness(&full_image)
+}
 
-            if monster_found {
-                monsters_found += 1;
+fn solve_part1_fast(assembly: &FastAssembly) -> i64 {
+    assembly.corner_tiles().iter().product()
+}
+
+// The assembled image in the forms the reports and the renderer need, however it was
+// assembled.
+struct AssembledImage {
+    tile_map: Vec<Vec<TileVariantKey>>,
+    tile_size: usize,
+    full_image: Vec<Vec<char>>,
+    bordered_image: Vec<Vec<char>>,
+}
+
+fn report_tile_orientations(assembled: &AssembledImage) {
+    let (full_image, tile_map) = (&assembled.full_image, &assembled.tile_map);
+    let monster_orientation = find_pattern_matches(full_image, &[Pattern::sea_monster()])
+        .first()
+        .map(|m| m.orientation);
+
+    // the image has to undo the monsters' orientation for them to read upright
+    let upright_orientation = monster_orientation.map(|o| o.inverse());
+    if let Some(upright) = upright_orientation {
+        println!("upright image: {}", upright);
+    }
+
+    for (tile_x, row) in tile_map.iter().enumerate() {
+        for (tile_y, (id, orientation)) in row.iter().enumerate() {
+            match upright_orientation {
+                Some(upright) => println!(
+                    "tile {} at ({}, {}): {} ({} in the upright image)",
+                    id,
+                    tile_x,
+                    tile_y,
+                    orientation,
+                    orientation.then(&upright)
+                ),
+                None => println!("tile {} at ({}, {}): {}", id, tile_x, tile_y, orientation),
             }
         }
     }
+}
 
-    if monsters_found == 0 {
-        None
-    } else {
-        let total_roughness: usize = full_image
-            .iter()
-            .map(|row| -> usize {
-                row.iter()
-                    .filter_map(|y| if *y == '#' { Some(1usize) } else { None })
-                    .sum()
-            })
-            .sum();
-        let monster_roughness = monsters_found * monster_indexes.len();
+fn report_pattern_matches(
+    assembled: &AssembledImage,
+    patterns: &[Pattern],
+    excluded_patterns: &ExcludedPatterns,
+) {
+    let full_image = &assembled.full_image;
+    let matches = find_pattern_matches(full_image, patterns);
 
-        assert!(total_roughness >= monster_roughness);
+    let describe_match = |m: &PatternMatch| {
+        format!(
+            "{} at ({}, {}), {}",
+            patterns[m.pattern_index].name, m.x, m.y, m.orientation
+        )
+    };
 
-        Some(total_roughness - monster_roughness)
+    for m in matches.iter() {
+        println!("match: {}", describe_match(m));
     }
+    for (index_a, index_b) in find_overlapping_matches(&matches) {
+        println!(
+            "overlap: {} / {}",
+            describe_match(&matches[index_a]),
+            describe_match(&matches[index_b])
+        );
+    }
+
+    let excluded_pattern_indexes = excluded_patterns.pattern_indexes(patterns);
+    println!(
+        "roughness: {}",
+        calculate_roughness(full_image, &matches, &excluded_pattern_indexes)
+    );
 }
 
-fn solve_part2(
-    tiles: &Vec<Tile>,
-    tile_variants: &HashMap<(i64, usize), TileVariant>,
-    tile_size: usize,
-) -> usize {
-    let edge_index = make_edge_index(tile_variants);
-    let mut tile_map = make_empty_tile_map(tiles);
-    let corner_tiles = find_corner_tiles(tiles, tile_variants, &edge_index);
+fn render_assembled_image(
+    assembled: &AssembledImage,
+    patterns: &[Pattern],
+    with_borders: bool,
+    options: RenderOptions,
+) -> render::Canvas {
+    let tile_size = assembled.tile_size;
+    let monster_cells = get_matched_cells(
+        &find_pattern_matches(&assembled.full_image, patterns),
+        &(0..patterns.len()).collect(),
+    );
+    let tile_ids: Vec<Vec<i64>> = assembled
+        .tile_map
+        .iter()
+        .map(|row| row.iter().map(|(id, _)| *id).collect())
+        .collect();
 
-    fill_tile_map(tile_variants, &edge_index, &mut tile_map, &corner_tiles);
+    if with_borders {
+        let borderless_tile_size = tile_size - 2;
+        let to_bordered = |coord: usize| {
+            (coord / borderless_tile_size) * tile_size + 1 + coord % borderless_tile_size
+        };
+        let bordered_monster_cells = monster_cells
+            .iter()
+            .map(|&(x, y)| (to_bordered(x), to_bordered(y)))
+            .collect();
+        let layout = TileLayout {
+            tile_ids,
+            tile_dimension: tile_size,
+            has_borders: true,
+        };
+
+        render_image(
+            &assembled.bordered_image,
+            &bordered_monster_cells,
+            &layout,
+            options,
+        )
+    } else {
+        let layout = TileLayout {
+            tile_ids,
+            tile_dimension: tile_size - 2,
+            has_borders: false,
+        };
 
-    let full_image = make_combined_image_from_tile_map(tile_variants, &tile_map, tile_size);
+        render_image(&assembled.full_image, &monster_cells, &layout, options)
+    }
+}
 
-    let (monster_x_dim, monster_y_dim, monster_indexes) = get_monster_image_indexes();
-
-    let mut rotated_full_image = full_image.clone();
-    let mut flipped_full_image = horizontal_flip_image(&full_image);
-    let mut rotations: usize = 0;
-    loop {
-        if let Some(solution) = find_sea_monster_data(
-            &rotated_full_image,
-            monster_x_dim,
-            monster_y_dim,
-            &monster_indexes,
-        )
-        .or_else(|| {
-            find_sea_monster_data(
-                &flipped_full_image,
-                monster_x_dim,
-                monster_y_dim,
-                &monster_indexes,
-            )
-        }) {
-            break solution;
+#[derive(Debug)]
+struct Options {
+    input_path: String,
+    fast_assembly: bool,
+    show_tiles: bool,
+    pattern_path: Option<String>,
+    excluded_patterns: ExcludedPatterns,
+    render_path: Option<String>,
+    render_borders: bool,
+    render_options: RenderOptions,
+    generate: bool,
+    generator_options: GeneratorOptions,
+    generator_image_path: Option<String>,
+    output_path: Option<String>,
+}
+
+fn parse_options(args: impl Iterator<Item = String>) -> Options {
+    let mut options = Options {
+        input_path: String::from(
+            "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day20/input.txt",
+        ),
+        fast_assembly: false,
+        show_tiles: false,
+        pattern_path: None,
+        excluded_patterns: ExcludedPatterns::All,
+        render_path: None,
+        render_borders: false,
+        render_options: RenderOptions {
+            scale: 4,
+            tile_grid: false,
+        },
+        generate: false,
+        generator_options: GeneratorOptions {
+            tiles_per_side: 12,
+            tile_size: 10,
+            density_percent: 30,
+            seed: 0,
+        },
+        generator_image_path: None,
+        output_path: None,
+    };
+
+    let mut args = args.peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fast" => options.fast_assembly = true,
+            "--show-tiles" => options.show_tiles = true,
+            "--patterns" => {
+                options.pattern_path = Some(args.next().expect("missing patterns path"))
+            }
+            "--exclude" => {
+                let value = args.next().expect("missing pattern name");
+                options.excluded_patterns = options.excluded_patterns.add(&value)
+            }
+            "--render" => options.render_path = Some(args.next().expect("missing render path")),
+            "--borders" => options.render_borders = true,
+            "--tile-grid" => options.render_options.tile_grid = true,
+            "--scale" => {
+                options.render_options.scale = args.next().expect("missing scale").parse().unwrap()
+            }
+            "--generate" => {
+                options.generate = true;
+                options.generator_options.tiles_per_side = args
+                    .next()
+                    .expect("missing tiles per side")
+                    .parse()
+                    .unwrap()
+            }
+            "--tile-size" => {
+                options.generator_options.tile_size =
+                    args.next().expect("missing tile size").parse().unwrap()
+            }
+            "--density" => {
+                options.generator_options.density_percent =
+                    args.next().expect("missing density").parse().unwrap()
+            }
+            "--seed" => {
+                options.generator_options.seed = args.next().expect("missing seed").parse().unwrap()
+            }
+            "--image" => {
+                options.generator_image_path = Some(args.next().expect("missing image path"))
+            }
+            "--output" => options.output_path = Some(args.next().expect("missing output path")),
+            _ if arg.starts_with("--") => panic!("unrecognized option: {}", arg),
+            _ => options.input_path = arg,
         }
+    }
 
-        rotated_full_image = right_rotate_image(&rotated_full_image);
-        flipped_full_image = right_rotate_image(&flipped_full_image);
+    options
+}
 
-        rotations += 1;
-        assert!(rotations < 4);
-    }
+fn generate(options: &Options) {
+    let image = options.generator_image_path.as_ref().map(|path| {
+        fs::read_to_string(path)
+            .unwrap()
+            .trim()
+            .lines()
+            .map(|line| line.trim().chars().collect())
+            .collect()
+    });
+    let puzzle = generate_puzzle(options.generator_options, image);
+
+    let output_path = options.output_path.as_ref().expect("missing --output path");
+    fs::write(output_path, puzzle.text + "\n").unwrap();
+
+    println!("{}", puzzle.expected_corner_product);
+    println!("{}", calculate_sea_roughness(&puzzle.image));
 }
 
 fn main() {
-    let contents = fs::read_to_string(
-        "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day20/input.txt",
-    )
-    .unwrap();
+    let options = parse_options(env::args().skip(1));
+    if options.generate {
+        generate(&options);
+        return;
+    }
+
+    let contents = fs::read_to_string(&options.input_path).unwrap();
 
     let tiles: Vec<_> = contents.trim().split("\n\n").map(parse_tile).collect();
-    let tile_variants = get_all_variants_for_tile_set(&tiles);
     let tile_size = tiles[0].image.len();
 
-    println!("{}", solve_part1(&tiles, &tile_variants, tile_size));
-    println!("{}", solve_part2(&tiles, &tile_variants, tile_size));
+    let wants_image =
+        options.show_tiles || options.pattern_path.is_some() || options.render_path.is_some();
+    let assembled = if options.fast_assembly {
+        let assembly = FastAssembly::new(&tiles);
+        let full_image = assembly.make_combined_image();
+        println!("{}", solve_part1_fast(&assembly));
+        println!("{}", calculate_sea_roughness(&full_image));
+        if !wants_image {
+            return;
+        }
+
+        AssembledImage {
+            tile_map: assembly.assemble(),
+            tile_size,
+            full_image,
+            bordered_image: assembly.make_bordered_image(),
+        }
+    } else {
+        let tile_variants = get_all_variants_for_tile_set(&tiles);
+        println!("{}", solve_part1(&tiles, &tile_variants, tile_size));
+        println!("{}", solve_part2(&tiles, &tile_variants, tile_size));
+        if !wants_image {
+            return;
+        }
+
+        let tile_map = assemble_tile_map(&tiles, &tile_variants);
+        AssembledImage {
+            full_image: make_combined_image_from_tile_map(&tile_variants, &tile_map, tile_size),
+            bordered_image: make_bordered_image_from_tile_map(&tile_variants, &tile_map, tile_size),
+            tile_map,
+            tile_size,
+        }
+    };
+
+    if options.show_tiles {
+        report_tile_orientations(&assembled);
+    }
+
+    let patterns = match &options.pattern_path {
+        Some(pattern_path) => {
+            let patterns = parse_patterns(&fs::read_to_string(pattern_path).unwrap());
+            report_pattern_matches(&assembled, &patterns, &options.excluded_patterns);
+            patterns
+        }
+        None => vec![Pattern::sea_monster()],
+    };
+
+    if let Some(render_path) = &options.render_path {
+        let canvas = render_assembled_image(
+            &assembled,
+            &patterns,
+            options.render_borders,
+            options.render_options,
+        );
+        let data = if render_path.ends_with(".ppm") {
+            canvas.to_ppm()
+        } else {
+            canvas.to_png()
+        };
+        fs::write(render_path, data).unwrap();
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{horizontal_flip_image, right_rotate_image};
+    use crate::{
+        assemble_tile_map, get_all_variants_for_tile_set, horizontal_flip_image,
+        make_bordered_image_from_tile_map, make_combined_image_from_tile_map, parse_tile,
+        right_rotate_image, solve_part2, Tile,
+    };
+
+    fn parse_sample_tiles() -> Vec<Tile> {
+        include_str!("../sample_input.txt")
+            .trim()
+            .split("\n\n")
+            .map(parse_tile)
+            .collect()
+    }
 
     #[test]
     fn test_rotation() {
@@ -564,4 +815,19 @@ mod tests {
         assert_eq!(reflected, horizontal_flip_image(&original));
         assert_eq!(original, horizontal_flip_image(&reflected));
     }
+
+    #[test]
+    fn test_sample_assembly_images() {
+        let tiles = parse_sample_tiles();
+        let tile_variants = get_all_variants_for_tile_set(&tiles);
+        let tile_map = assemble_tile_map(&tiles, &tile_variants);
+
+        let full_image = make_combined_image_from_tile_map(&tile_variants, &tile_map, 10);
+        let bordered_image = make_bordered_image_from_tile_map(&tile_variants, &tile_map, 10);
+        assert_eq!(24, full_image.len());
+        assert_eq!(30, bordered_image.len());
+        assert_eq!(full_image[0][..8], bordered_image[1][1..9]);
+
+        assert_eq!(273, solve_part2(&tiles, &tile_variants, 10));
+    }
 }
@@ -0,0 +1,232 @@
+use std::fmt;
+
+// An element of the symmetry group of the square (D4): the image is flipped top-to-bottom
+// first (if at all), then right-rotated the given number of times.
+// Indexes 0..=3 are the unflipped orientations and 4..=7 the flipped ones,
+// matching the order in which the tile variants were originally generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Orientation {
+    flipped: bool,
+    rotations: usize,
+}
+
+impl Orientation {
+    pub const IDENTITY: Orientation = Orientation {
+        flipped: false,
+        rotations: 0,
+    };
+
+    pub fn new(flipped: bool, rotations: usize) -> Orientation {
+        Orientation {
+            flipped,
+            rotations: rotations % 4,
+        }
+    }
+
+    pub fn all() -> impl Iterator<Item = Orientation> {
+        (0..8).map(Orientation::from_index)
+    }
+
+    pub fn from_index(index: usize) -> Orientation {
+        assert!(index < 8);
+        Orientation::new(index >= 4, index % 4)
+    }
+
+    pub fn index(&self) -> usize {
+        if self.flipped {
+            4 + self.rotations
+        } else {
+            self.rotations
+        }
+    }
+
+    // The orientation equivalent to applying self first, then next.
+    pub fn then(&self, next: &Orientation) -> Orientation {
+        // flipping turns right rotations into left rotations: F R = R^-1 F
+        let carried_rotations = if next.flipped {
+            4 - self.rotations
+        } else {
+            self.rotations
+        };
+        Orientation::new(
+            self.flipped != next.flipped,
+            next.rotations + carried_rotations,
+        )
+    }
+
+    pub fn inverse(&self) -> Orientation {
+        if self.flipped {
+            *self // every flipped orientation is a reflection, and its own inverse
+        } else {
+            Orientation::new(false, 4 - self.rotations)
+        }
+    }
+
+    // Where the cell at (x, y) of the original image ends up after applying this orientation.
+    pub fn map_coordinate(&self, dimension: usize, x: usize, y: usize) -> (usize, usize) {
+        let max_coord = dimension - 1;
+        let (mut x, mut y) = (x, y);
+        if self.flipped {
+            x = max_coord - x;
+        }
+        for _ in 0..self.rotations {
+            let next = (y, max_coord - x);
+            x = next.0;
+            y = next.1;
+        }
+        (x, y)
+    }
+
+    // Where the cell at (x, y) of the oriented image comes from in the original image.
+    pub fn source_coordinate(&self, dimension: usize, x: usize, y: usize) -> (usize, usize) {
+        self.inverse().map_coordinate(dimension, x, y)
+    }
+
+    pub fn apply<T: Clone>(&self, image: &[Vec<T>]) -> Vec<Vec<T>> {
+        let dimension = image.len();
+        assert!(image.iter().all(|row| row.len() == dimension));
+
+        (0..dimension)
+            .map(|x| {
+                (0..dimension)
+                    .map(|y| {
+                        let (source_x, source_y) = self.source_coordinate(dimension, x, y);
+                        image[source_x][source_y].clone()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Edges are given and returned in Edge::variants() order, each read in the same direction
+    // as TileVariant::get_edge(): left to right for top and bottom, top to bottom for the sides.
+    pub fn apply_to_edges<E: Clone>(&self, edges: &[E; 4], reverse: impl Fn(&E) -> E) -> [E; 4] {
+        let [mut top, mut right, mut bottom, mut left] = edges.clone();
+        if self.flipped {
+            std::mem::swap(&mut top, &mut bottom);
+            left = reverse(&left);
+            right = reverse(&right);
+        }
+        for _ in 0..self.rotations {
+            let next_top = reverse(&left);
+            let next_bottom = reverse(&right);
+            left = bottom;
+            right = top;
+            top = next_top;
+            bottom = next_bottom;
+        }
+        [top, right, bottom, left]
+    }
+}
+
+impl fmt::Display for Orientation {
+    // Described as a clockwise rotation followed by a top-to-bottom flip, since that reads
+    // more naturally than the equivalent flip-then-rotate we store.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rotations = if self.flipped {
+            (4 - self.rotations) % 4
+        } else {
+            self.rotations
+        };
+
+        match (rotations, self.flipped) {
+            (0, false) => write!(f, "not rotated"),
+            (0, true) => write!(f, "flipped"),
+            (_, false) => write!(f, "rotated {}°", rotations * 90),
+            (_, true) => write!(f, "rotated {}°, flipped", rotations * 90),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Orientation;
+
+    fn make_image() -> Vec<Vec<char>> {
+        ["abc", "def", "ghi"]
+            .iter()
+            .map(|row| row.chars().collect())
+            .collect()
+    }
+
+    fn read_edges(image: &[Vec<char>]) -> [Vec<char>; 4] {
+        [
+            image[0].clone(),
+            image.iter().map(|row| row[row.len() - 1]).collect(),
+            image[image.len() - 1].clone(),
+            image.iter().map(|row| row[0]).collect(),
+        ]
+    }
+
+    #[test]
+    fn test_group_laws() {
+        let image = make_image();
+
+        for a in Orientation::all() {
+            assert_eq!(a, Orientation::from_index(a.index()));
+            assert_eq!(a, a.then(&Orientation::IDENTITY));
+            assert_eq!(a, Orientation::IDENTITY.then(&a));
+            assert_eq!(Orientation::IDENTITY, a.then(&a.inverse()));
+            assert_eq!(Orientation::IDENTITY, a.inverse().then(&a));
+
+            for b in Orientation::all() {
+                assert_eq!(b.apply(&a.apply(&image)), a.then(&b).apply(&image));
+
+                for c in Orientation::all() {
+                    assert_eq!(a.then(&b).then(&c), a.then(&b.then(&c)));
+                }
+            }
+        }
+
+        let distinct_images: std::collections::HashSet<_> =
+            Orientation::all().map(|o| o.apply(&image)).collect();
+        assert_eq!(8, distinct_images.len());
+    }
+
+    #[test]
+    fn test_apply_matches_image_helpers() {
+        let image = make_image();
+
+        let mut rotated = image.clone();
+        let mut flipped = crate::horizontal_flip_image(&image);
+        for rotations in 0..4 {
+            assert_eq!(rotated, Orientation::new(false, rotations).apply(&image));
+            assert_eq!(flipped, Orientation::new(true, rotations).apply(&image));
+            rotated = crate::right_rotate_image(&rotated);
+            flipped = crate::right_rotate_image(&flipped);
+        }
+    }
+
+    #[test]
+    fn test_apply_to_edges() {
+        let image = make_image();
+        let edges = read_edges(&image);
+
+        for orientation in Orientation::all() {
+            let reversed = |edge: &Vec<char>| edge.iter().rev().copied().collect();
+            assert_eq!(
+                read_edges(&orientation.apply(&image)),
+                orientation.apply_to_edges(&edges, reversed)
+            );
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("not rotated", Orientation::IDENTITY.to_string());
+        assert_eq!("rotated 90°", Orientation::new(false, 1).to_string());
+        assert_eq!("flipped", Orientation::new(true, 0).to_string());
+        assert_eq!(
+            "rotated 90°, flipped",
+            Orientation::new(true, 3).to_string()
+        );
+
+        // the description must be accurate: rotating and then flipping gives the same image
+        let image = make_image();
+        let rotate_then_flip = Orientation::new(false, 1).then(&Orientation::new(true, 0));
+        assert_eq!(
+            rotate_then_flip.apply(&image),
+            Orientation::new(true, 3).apply(&image)
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::{BTreeSet, HashSet};
+
+use crate::orientation::Orientation;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub name: String,
+    pub x_dim: usize,
+    pub y_dim: usize,
+    pub cells: Vec<(usize, usize)>, // coordinates of the '#' cells, every other cell is ignored
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternMatch {
+    pub pattern_index: usize,
+    pub orientation: Orientation, // how the pattern was oriented to match the image
+    pub x: usize,
+    pub y: usize,
+    pub cells: Vec<(usize, usize)>, // matched image cells
+}
+
+impl Pattern {
+    pub fn parse(name: &str, rows: &[&str]) -> Pattern {
+        let cells: Vec<(usize, usize)> = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(row_index, row)| {
+                row.chars()
+                    .enumerate()
+                    .filter(|(_, c)| *c == '#')
+                    .map(move |(col_index, _)| (row_index, col_index))
+            })
+            .collect();
+        assert!(!cells.is_empty(), "pattern {} has no '#' cells", name);
+
+        Pattern {
+            name: name.to_string(),
+            x_dim: cells.iter().map(|(x, _)| x + 1).max().unwrap(),
+            y_dim: cells.iter().map(|(_, y)| y + 1).max().unwrap(),
+            cells,
+        }
+    }
+
+    pub fn sea_monster() -> Pattern {
+        Pattern::parse(
+            "sea monster",
+            &[
+                "                  # ",
+                "#    ##    ##    ###",
+                " #  #  #  #  #  #   ",
+            ],
+        )
+    }
+
+    fn right_rotated(&self) -> Pattern {
+        // same mapping as right_rotate_image(), but for non-square shapes
+        let mut cells: Vec<_> = self
+            .cells
+            .iter()
+            .map(|&(x, y)| (y, self.x_dim - 1 - x))
+            .collect();
+        cells.sort_unstable();
+
+        Pattern {
+            name: self.name.clone(),
+            x_dim: self.y_dim,
+            y_dim: self.x_dim,
+            cells,
+        }
+    }
+
+    fn horizontally_flipped(&self) -> Pattern {
+        let mut cells: Vec<_> = self
+            .cells
+            .iter()
+            .map(|&(x, y)| (self.x_dim - 1 - x, y))
+            .collect();
+        cells.sort_unstable();
+
+        Pattern {
+            name: self.name.clone(),
+            x_dim: self.x_dim,
+            y_dim: self.y_dim,
+            cells,
+        }
+    }
+
+    // All 8 orientations, in Orientation::all() order. Symmetric patterns produce duplicates.
+    pub fn orientations(&self) -> Vec<Pattern> {
+        let mut result = vec![];
+
+        let mut rotated = self.clone();
+        for _ in 0..=3 {
+            let next = rotated.right_rotated();
+            result.push(rotated);
+            rotated = next;
+        }
+
+        let mut flipped = self.horizontally_flipped();
+        for _ in 0..=3 {
+            let next = flipped.right_rotated();
+            result.push(flipped);
+            flipped = next;
+        }
+
+        result
+    }
+}
+
+// Blocks separated by blank lines, each a "Pattern <name>:" header followed by the shape rows.
+pub fn parse_patterns(contents: &str) -> Vec<Pattern> {
+    contents
+        .trim_matches('\n')
+        .split("\n\n")
+        .map(|block| {
+            let mut lines = block.lines();
+
+            let header = lines.next().unwrap().trim();
+            assert!(header.starts_with("Pattern "));
+            assert!(header.ends_with(':'));
+
+            let rows: Vec<&str> = lines.collect();
+            Pattern::parse(&header["Pattern ".len()..header.len() - 1], &rows)
+        })
+        .collect()
+}
+
+pub fn find_pattern_matches(image: &[Vec<char>], patterns: &[Pattern]) -> Vec<PatternMatch> {
+    let mut result = vec![];
+
+    for (pattern_index, pattern) in patterns.iter().enumerate() {
+        let mut seen_cell_sets: HashSet<BTreeSet<(usize, usize)>> = HashSet::new();
+
+        for (orientation, oriented) in Orientation::all().zip(pattern.orientations().iter()) {
+            if oriented.x_dim > image.len() || oriented.y_dim > image[0].len() {
+                continue;
+            }
+
+            for root_x in 0..=(image.len() - oriented.x_dim) {
+                for root_y in 0..=(image[0].len() - oriented.y_dim) {
+                    let pattern_found = oriented
+                        .cells
+                        .iter()
+                        .all(|(x, y)| image[root_x + x][root_y + y] == '#');
+                    if !pattern_found {
+                        continue;
+                    }
+
+                    let cells: Vec<_> = oriented
+                        .cells
+                        .iter()
+                        .map(|(x, y)| (root_x + x, root_y + y))
+                        .collect();
+                    if seen_cell_sets.insert(cells.iter().copied().collect()) {
+                        result.push(PatternMatch {
+                            pattern_index,
+                            orientation,
+                            x: root_x,
+                            y: root_y,
+                            cells,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    result
+}
+
+// Pairs of indexes into the matches slice whose matched cells intersect.
+pub fn find_overlapping_matches(matches: &[PatternMatch]) -> Vec<(usize, usize)> {
+    let cell_sets: Vec<HashSet<(usize, usize)>> = matches
+        .iter()
+        .map(|m| m.cells.iter().copied().collect())
+        .collect();
+
+    let mut result = vec![];
+    for (index_a, cells_a) in cell_sets.iter().enumerate() {
+        for (index_b, cells_b) in cell_sets.iter().enumerate().skip(index_a + 1) {
+            if !cells_a.is_disjoint(cells_b) {
+                result.push((index_a, index_b));
+            }
+        }
+    }
+
+    result
+}
+
+pub fn get_matched_cells(
+    matches: &[PatternMatch],
+    pattern_indexes: &HashSet<usize>,
+) -> HashSet<(usize, usize)> {
+    matches
+        .iter()
+        .filter(|m| pattern_indexes.contains(&m.pattern_index))
+        .flat_map(|m| m.cells.iter().copied())
+        .collect()
+}
+
+// Which patterns' matches to leave out of the roughness. Given on the command line as
+// "all", "none", or pattern names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExcludedPatterns {
+    All,
+    Named(Vec<String>), // possibly none at all
+}
+
+impl ExcludedPatterns {
+    // Adds one command-line value to the selection made so far.
+    pub fn add(self, value: &str) -> ExcludedPatterns {
+        match (self, value) {
+            (_, "all") => ExcludedPatterns::All,
+            (_, "none") => ExcludedPatterns::Named(vec![]),
+            (ExcludedPatterns::All, name) => ExcludedPatterns::Named(vec![name.to_string()]),
+            (ExcludedPatterns::Named(mut names), name) => {
+                names.push(name.to_string());
+                ExcludedPatterns::Named(names)
+            }
+        }
+    }
+
+    pub fn pattern_indexes(&self, patterns: &[Pattern]) -> HashSet<usize> {
+        match self {
+            ExcludedPatterns::All => (0..patterns.len()).collect(),
+            ExcludedPatterns::Named(names) => names
+                .iter()
+                .map(|name| {
+                    patterns
+                        .iter()
+                        .position(|p| &p.name == name)
+                        .unwrap_or_else(|| panic!("unknown pattern: {}", name))
+                })
+                .collect(),
+        }
+    }
+}
+
+// Number of '#' cells not covered by any match of the excluded patterns.
+pub fn calculate_roughness(
+    image: &[Vec<char>],
+    matches: &[PatternMatch],
+    excluded_pattern_indexes: &HashSet<usize>,
+) -> usize {
+    let total_roughness = image
+        .iter()
+        .map(|row| row.iter().filter(|c| **c == '#').count())
+        .sum::<usize>();
+    let excluded_cells = get_matched_cells(matches, excluded_pattern_indexes);
+
+    total_roughness - excluded_cells.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{
+        calculate_roughness, find_overlapping_matches, find_pattern_matches, parse_patterns,
+        ExcludedPatterns, Pattern,
+    };
+
+    fn make_image(rows: &[&str]) -> Vec<Vec<char>> {
+        rows.iter().map(|row| row.chars().collect()).collect()
+    }
+
+    #[test]
+    fn test_parse_patterns() {
+        let patterns = parse_patterns("Pattern bar:\n###\n\nPattern corner:\n#\n##\n");
+
+        assert_eq!(2, patterns.len());
+        assert_eq!("bar", patterns[0].name);
+        assert_eq!((1, 3), (patterns[0].x_dim, patterns[0].y_dim));
+        assert_eq!(vec![(0, 0), (1, 0), (1, 1)], patterns[1].cells);
+        assert_eq!(Pattern::sea_monster().cells.len(), 15);
+    }
+
+    #[test]
+    fn test_matches_in_every_orientation() {
+        let image = make_image(&["#...", "##..", "...#", "..##"]);
+        let patterns = parse_patterns("Pattern corner:\n#\n##");
+
+        let matches = find_pattern_matches(&image, &patterns);
+        let mut roots: Vec<_> = matches
+            .iter()
+            .map(|m| (m.x, m.y, m.orientation.index()))
+            .collect();
+        roots.sort_unstable();
+
+        // the second corner is the first one rotated three times
+        assert_eq!(vec![(0, 0, 0), (2, 2, 3)], roots);
+        assert!(find_overlapping_matches(&matches).is_empty());
+    }
+
+    #[test]
+    fn test_overlaps_and_roughness() {
+        let image = make_image(&["###", "#.."]);
+        let patterns = parse_patterns("Pattern bar:\n###\n\nPattern corner:\n##\n#");
+
+        let matches = find_pattern_matches(&image, &patterns);
+        assert_eq!(2, matches.len());
+        assert_eq!(vec![(0, 1)], find_overlapping_matches(&matches));
+
+        let bar_only: HashSet<usize> = vec![0].into_iter().collect();
+        let both: HashSet<usize> = vec![0, 1].into_iter().collect();
+        assert_eq!(4, calculate_roughness(&image, &matches, &HashSet::new()));
+        assert_eq!(1, calculate_roughness(&image, &matches, &bar_only));
+        assert_eq!(0, calculate_roughness(&image, &matches, &both));
+    }
+
+    #[test]
+    fn test_excluded_patterns() {
+        let patterns = parse_patterns("Pattern bar:\n###\n\nPattern corner:\n##\n#");
+        let indexes = |values: &[&str]| {
+            let excluded = values
+                .iter()
+                .fold(ExcludedPatterns::All, |excluded, value| excluded.add(value));
+            let mut indexes: Vec<usize> = excluded.pattern_indexes(&patterns).into_iter().collect();
+            indexes.sort_unstable();
+            indexes
+        };
+
+        assert_eq!(vec![0, 1], indexes(&[]));
+        assert_eq!(Vec::<usize>::new(), indexes(&["none"]));
+        assert_eq!(vec![1], indexes(&["corner"]));
+        assert_eq!(vec![0, 1], indexes(&["corner", "bar"]));
+        assert_eq!(vec![0, 1], indexes(&["none", "all"]));
+    }
+}
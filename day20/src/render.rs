@@ -0,0 +1,272 @@
+use std::collections::HashSet;
+
+pub type Color = [u8; 3];
+
+const WATER_COLOR: Color = [16, 42, 92];
+const WAVE_COLOR: Color = [120, 170, 220];
+const BORDER_WATER_COLOR: Color = [40, 40, 48];
+const BORDER_WAVE_COLOR: Color = [110, 110, 120];
+const MONSTER_COLOR: Color = [255, 96, 32];
+const GRID_COLOR: Color = [250, 210, 60];
+const TEXT_COLOR: Color = [255, 255, 255];
+const TEXT_SHADOW_COLOR: Color = [0, 0, 0];
+
+// 3x5 bitmap digits, one row per entry, most significant of the low 3 bits is leftmost
+const DIGIT_FONT: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+#[derive(Debug)]
+pub struct TileLayout {
+    pub tile_ids: Vec<Vec<i64>>,
+    pub tile_dimension: usize, // side of each tile in image cells, as present in the image
+    pub has_borders: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    pub scale: usize,
+    pub tile_grid: bool,
+}
+
+#[derive(Debug)]
+pub struct Canvas {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize, background: Color) -> Canvas {
+        Canvas {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Color) {
+        for py in y..(y + height) {
+            for px in x..(x + width) {
+                self.set(px, py, color);
+            }
+        }
+    }
+
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut result = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for pixel in self.pixels.iter() {
+            result.extend_from_slice(pixel);
+        }
+        result
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut result = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        header.extend_from_slice(&[8, 2, 0, 0, 0]); // 8-bit RGB, no interlacing
+        write_png_chunk(&mut result, b"IHDR", &header);
+
+        let mut scanlines = Vec::with_capacity(self.height * (1 + 3 * self.width));
+        for row in self.pixels.chunks(self.width) {
+            scanlines.push(0); // filter type: none
+            for pixel in row {
+                scanlines.extend_from_slice(pixel);
+            }
+        }
+        write_png_chunk(&mut result, b"IDAT", &zlib_store(&scanlines));
+        write_png_chunk(&mut result, b"IEND", &[]);
+
+        result
+    }
+}
+
+fn write_png_chunk(output: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let crc_start = output.len();
+    output.extend_from_slice(kind);
+    output.extend_from_slice(data);
+    let crc = crc32(&output[crc_start..]);
+    output.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// zlib stream made of uncompressed deflate blocks -- larger files, but no dependencies
+fn zlib_store(data: &[u8]) -> Vec<u8> {
+    let mut result = vec![0x78, 0x01];
+
+    let mut blocks = data.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        result.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        let length = block.len() as u16;
+        result.push(if is_final { 1 } else { 0 });
+        result.extend_from_slice(&length.to_le_bytes());
+        result.extend_from_slice(&(!length).to_le_bytes());
+        result.extend_from_slice(block);
+    }
+
+    result.extend_from_slice(&adler32(data).to_be_bytes());
+    result
+}
+
+fn is_border_cell(layout: &TileLayout, x: usize, y: usize) -> bool {
+    let last = layout.tile_dimension - 1;
+    let (tile_x, tile_y) = (x % layout.tile_dimension, y % layout.tile_dimension);
+    layout.has_borders && (tile_x == 0 || tile_x == last || tile_y == 0 || tile_y == last)
+}
+
+fn draw_number(canvas: &mut Canvas, x: usize, y: usize, font_scale: usize, number: i64) {
+    let text = number.to_string();
+    for (index, digit) in text.chars().filter_map(|c| c.to_digit(10)).enumerate() {
+        let glyph = &DIGIT_FONT[digit as usize];
+        let glyph_x = x + index * 4 * font_scale;
+        for (row_index, row) in glyph.iter().enumerate() {
+            for col_index in 0..3 {
+                if row & (0b100 >> col_index) != 0 {
+                    let (px, py) = (glyph_x + col_index * font_scale, y + row_index * font_scale);
+                    canvas.fill_rect(px + 1, py + 1, font_scale, font_scale, TEXT_SHADOW_COLOR);
+                    canvas.fill_rect(px, py, font_scale, font_scale, TEXT_COLOR);
+                }
+            }
+        }
+    }
+}
+
+fn draw_tile_grid(canvas: &mut Canvas, layout: &TileLayout, scale: usize) {
+    let tile_pixels = layout.tile_dimension * scale;
+
+    for (tile_x, row) in layout.tile_ids.iter().enumerate() {
+        for (tile_y, tile_id) in row.iter().enumerate() {
+            let (left, top) = (tile_y * tile_pixels, tile_x * tile_pixels);
+
+            canvas.fill_rect(left, top, tile_pixels, 1, GRID_COLOR);
+            canvas.fill_rect(left, top, 1, tile_pixels, GRID_COLOR);
+
+            let digits = tile_id.to_string().len();
+            let font_scale = ((tile_pixels.saturating_sub(4)) / (digits * 4)).clamp(1, scale);
+            draw_number(canvas, left + 2, top + 2, font_scale, *tile_id);
+        }
+    }
+
+    let (width, height) = (canvas.width, canvas.height);
+    canvas.fill_rect(0, height - 1, width, 1, GRID_COLOR);
+    canvas.fill_rect(width - 1, 0, 1, height, GRID_COLOR);
+}
+
+pub fn render_image(
+    image: &[Vec<char>],
+    highlighted: &HashSet<(usize, usize)>,
+    layout: &TileLayout,
+    options: RenderOptions,
+) -> Canvas {
+    let scale = options.scale.max(1);
+    let mut canvas = Canvas::new(image[0].len() * scale, image.len() * scale, WATER_COLOR);
+
+    for (x, row) in image.iter().enumerate() {
+        for (y, cell) in row.iter().enumerate() {
+            let border = is_border_cell(layout, x, y);
+            let color = match (*cell, border) {
+                _ if highlighted.contains(&(x, y)) => MONSTER_COLOR,
+                ('#', false) => WAVE_COLOR,
+                ('#', true) => BORDER_WAVE_COLOR,
+                (_, false) => WATER_COLOR,
+                (_, true) => BORDER_WATER_COLOR,
+            };
+            canvas.fill_rect(y * scale, x * scale, scale, scale, color);
+        }
+    }
+
+    if options.tile_grid {
+        draw_tile_grid(&mut canvas, layout, scale);
+    }
+
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{adler32, crc32, render_image, Canvas, RenderOptions, TileLayout, MONSTER_COLOR};
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(0xae42_6082, crc32(b"IEND"));
+        assert_eq!(0x11e6_0398, adler32(b"Wikipedia"));
+    }
+
+    #[test]
+    fn test_image_encodings() {
+        let mut canvas = Canvas::new(3, 2, [0, 0, 0]);
+        canvas.set(2, 1, [1, 2, 3]);
+
+        let ppm = canvas.to_ppm();
+        assert!(ppm.starts_with(b"P6\n3 2\n255\n"));
+        assert_eq!(&[1, 2, 3], &ppm[ppm.len() - 3..]);
+
+        let png = canvas.to_png();
+        assert_eq!(b"\x89PNG\r\n\x1a\n", &png[..8]);
+        assert_eq!(b"IEND", &png[png.len() - 8..png.len() - 4]);
+    }
+
+    #[test]
+    fn test_highlighted_cells_are_colored() {
+        let image = vec![vec!['#', '.'], vec!['.', '#']];
+        let highlighted: HashSet<_> = vec![(1, 1)].into_iter().collect();
+        let layout = TileLayout {
+            tile_ids: vec![vec![1]],
+            tile_dimension: 2,
+            has_borders: false,
+        };
+        let options = RenderOptions {
+            scale: 2,
+            tile_grid: false,
+        };
+
+        let canvas = render_image(&image, &highlighted, &layout, options);
+        assert_eq!((4, 4), (canvas.width, canvas.height));
+        assert_eq!(MONSTER_COLOR, canvas.pixels[3 * canvas.width + 3]);
+        assert_ne!(MONSTER_COLOR, canvas.pixels[0]);
+    }
+}
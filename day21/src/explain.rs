@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use textformat::json_string;
+
+use crate::matching::{AllergenAssignment, Assignment, ForcedPair};
+
+// A food line that mentions the allergen, and the candidate foods left after intersecting it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NarrowingStep<'a> {
+    pub allergen: &'a str,
+    pub line_number: usize, // 1-based, as in an editor
+    pub remaining_foods: BTreeSet<&'a str>,
+}
+
+#[derive(Debug)]
+pub struct Derivation<'a> {
+    pub narrowing: Vec<NarrowingStep<'a>>,
+    pub forced: Vec<ForcedPair<'a>>,
+    pub outcome: AllergenAssignment<'a>,
+}
+
+fn join_names<'a>(names: impl IntoIterator<Item = &'a &'a str>) -> String {
+    let names: Vec<&str> = names.into_iter().copied().collect();
+    if names.is_empty() {
+        String::from("(none)")
+    } else {
+        names.join(", ")
+    }
+}
+
+fn json_string_array<'a>(values: impl IntoIterator<Item = &'a &'a str>) -> String {
+    let values: Vec<String> = values.into_iter().map(|value| json_string(value)).collect();
+    format!("[{}]", values.join(", "))
+}
+
+fn json_assignment(assignment: &Assignment) -> String {
+    let pairs: Vec<String> = assignment
+        .iter()
+        .map(|(allergen, food)| format!("{}: {}", json_string(allergen), json_string(food)))
+        .collect();
+    format!("{{{}}}", pairs.join(", "))
+}
+
+impl<'a> Derivation<'a> {
+    fn narrowing_by_allergen(&self) -> BTreeMap<&'a str, Vec<&NarrowingStep<'a>>> {
+        let mut result: BTreeMap<&str, Vec<&NarrowingStep>> = BTreeMap::new();
+        for step in self.narrowing.iter() {
+            result.entry(step.allergen).or_default().push(step);
+        }
+        result
+    }
+
+    pub fn to_proof(&self) -> String {
+        let mut lines = vec![String::from("Candidate foods from the food lines:")];
+
+        for (allergen, steps) in self.narrowing_by_allergen() {
+            lines.push(format!("  {}:", allergen));
+            for (index, step) in steps.iter().enumerate() {
+                let verb = if index == 0 {
+                    "starts with"
+                } else {
+                    "narrows to"
+                };
+                lines.push(format!(
+                    "    line {} {} {}",
+                    step.line_number,
+                    verb,
+                    join_names(step.remaining_foods.iter())
+                ));
+            }
+        }
+
+        lines.push(String::from("Forced assignments:"));
+        if self.forced.is_empty() {
+            lines.push(String::from("  (none)"));
+        }
+        for pair in self.forced.iter() {
+            let consequence = if pair.eliminated_from.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    ", so it is eliminated from {}",
+                    join_names(pair.eliminated_from.iter())
+                )
+            };
+            lines.push(format!(
+                "  {} is in {}, its only remaining candidate{}",
+                pair.allergen, pair.food, consequence
+            ));
+        }
+
+        lines.push(format!("Result: {}", self.outcome));
+        lines.join("\n")
+    }
+
+    pub fn to_json(&self) -> String {
+        let narrowing: Vec<String> = self
+            .narrowing_by_allergen()
+            .iter()
+            .map(|(allergen, steps)| {
+                let steps: Vec<String> = steps
+                    .iter()
+                    .map(|step| {
+                        format!(
+                            "{{\"line\": {}, \"remaining_foods\": {}}}",
+                            step.line_number,
+                            json_string_array(step.remaining_foods.iter())
+                        )
+                    })
+                    .collect();
+                format!("{}: [{}]", json_string(allergen), steps.join(", "))
+            })
+            .collect();
+
+        let forced: Vec<String> = self
+            .forced
+            .iter()
+            .map(|pair| {
+                format!(
+                    "{{\"allergen\": {}, \"food\": {}, \"eliminated_from\": {}}}",
+                    json_string(pair.allergen),
+                    json_string(pair.food),
+                    json_string_array(pair.eliminated_from.iter())
+                )
+            })
+            .collect();
+
+        let outcome = match &self.outcome {
+            AllergenAssignment::Unique(assignment) => format!(
+                "{{\"kind\": \"unique\", \"assignment\": {}}}",
+                json_assignment(assignment)
+            ),
+            AllergenAssignment::Ambiguous {
+                assignments,
+                truncated,
+            } => {
+                let assignments: Vec<String> = assignments.iter().map(json_assignment).collect();
+                format!(
+                    "{{\"kind\": \"ambiguous\", \"truncated\": {}, \"assignments\": [{}]}}",
+                    truncated,
+                    assignments.join(", ")
+                )
+            }
+            AllergenAssignment::Impossible { allergens, foods } => format!(
+                "{{\"kind\": \"impossible\", \"allergens\": {}, \"foods\": {}}}",
+                json_string_array(allergens.iter()),
+                json_string_array(foods.iter())
+            ),
+        };
+
+        format!(
+            "{{\"narrowing\": {{{}}}, \"forced\": [{}], \"outcome\": {}}}",
+            narrowing.join(", "),
+            forced.join(", "),
+            outcome
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::json_string;
+    use crate::{assemble_initial_allergen_data, parse_line, solve_part2};
+
+    #[test]
+    fn test_json_string_escaping() {
+        assert_eq!(r#""plain""#, json_string("plain"));
+        assert_eq!(r#""a \"b\" \\ c\n""#, json_string("a \"b\" \\ c\n"));
+    }
+
+    #[test]
+    fn test_sample_derivation() {
+        let data: Vec<_> = include_str!("../sample_input.txt")
+            .trim()
+            .lines()
+            .map(parse_line)
+            .collect();
+        let mut narrowing = vec![];
+        let (allergen_to_food, _) = assemble_initial_allergen_data(&data, &mut narrowing);
+        let derivation = solve_part2(&allergen_to_food, narrowing);
+
+        let dairy_lines: Vec<_> = derivation
+            .narrowing
+            .iter()
+            .filter(|step| step.allergen == "dairy")
+            .map(|step| step.line_number)
+            .collect();
+        assert_eq!(vec![1, 2], dairy_lines);
+
+        let proof = derivation.to_proof();
+        assert!(proof.contains("    line 2 narrows to mxmxvkd"));
+        assert!(proof.contains(
+            "  dairy is in mxmxvkd, its only remaining candidate, so it is eliminated from fish"
+        ));
+        assert!(proof.ends_with("Result: mxmxvkd,sqjhc,fvjkl"));
+
+        let json = derivation.to_json();
+        assert!(json.contains(r#""dairy": [{"line": 1, "remaining_foods": ["kfcds", "mxmxvkd", "nhms", "sqjhc"]}, {"line": 2, "remaining_foods": ["mxmxvkd"]}]"#));
+        assert!(json.ends_with(r#""outcome": {"kind": "unique", "assignment": {"dairy": "mxmxvkd", "fish": "sqjhc", "soy": "fvjkl"}}}"#));
+    }
+}
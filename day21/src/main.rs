@@ -1,8 +1,14 @@
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
-    fs,
+    collections::{HashMap, HashSet},
+    env, fs,
 };
 
+use explain::{Derivation, NarrowingStep};
+use matching::{solve_allergen_assignment, AllergenAssignment, Candidates};
+
+mod explain;
+mod matching;
+
 fn parse_line(line: &str) -> (Vec<&str>, Vec<&str>) {
     let components: Vec<_> = line
         .strip_suffix(")")
@@ -17,14 +23,14 @@ fn parse_line(line: &str) -> (Vec<&str>, Vec<&str>) {
     (foods, allergens)
 }
 
+type MapOfSets<'a> = HashMap<&'a str, HashSet<&'a str>>;
+
 fn assemble_initial_allergen_data<'a>(
-    data: &Vec<(Vec<&'a str>, Vec<&'a str>)>,
-) -> (
-    HashMap<&'a str, HashSet<&'a str>>,
-    HashMap<&'a str, HashSet<&'a str>>,
-) {
+    data: &[(Vec<&'a str>, Vec<&'a str>)],
+    narrowing: &mut Vec<NarrowingStep<'a>>,
+) -> (MapOfSets<'a>, MapOfSets<'a>) {
     let mut allergen_to_food: HashMap<&str, HashSet<&str>> = HashMap::new();
-    for (foods, allergens) in data {
+    for (line_index, (foods, allergens)) in data.iter().enumerate() {
         let mentioned_foods: HashSet<&str> = foods.iter().cloned().collect();
         for &allergen in allergens {
             let current_suspect_foods = allergen_to_food.get(allergen).unwrap_or(&mentioned_foods);
@@ -33,6 +39,11 @@ fn assemble_initial_allergen_data<'a>(
                 .cloned()
                 .collect();
 
+            narrowing.push(NarrowingStep {
+                allergen,
+                line_number: line_index + 1,
+                remaining_foods: remaining_suspect_foods.iter().copied().collect(),
+            });
             allergen_to_food.insert(allergen, remaining_suspect_foods);
         }
     }
@@ -40,31 +51,24 @@ fn assemble_initial_allergen_data<'a>(
     let mut food_to_possible_allergens = transpose_map_of_sets(&allergen_to_food);
     for (foods, _) in data {
         for &food in foods {
-            food_to_possible_allergens
-                .entry(food)
-                .or_insert(HashSet::new());
+            food_to_possible_allergens.entry(food).or_default();
         }
     }
 
     (allergen_to_food, food_to_possible_allergens)
 }
 
-fn transpose_map_of_sets<'a>(
-    map_of_sets: &HashMap<&'a str, HashSet<&'a str>>,
-) -> HashMap<&'a str, HashSet<&'a str>> {
-    let mut result = HashMap::new();
+fn transpose_map_of_sets<'a>(map_of_sets: &MapOfSets<'a>) -> MapOfSets<'a> {
+    let mut result: MapOfSets = HashMap::new();
     for (&key, values) in map_of_sets.iter() {
         for &value in values {
-            result.entry(value).or_insert(HashSet::new()).insert(key);
+            result.entry(value).or_default().insert(key);
         }
     }
     result
 }
 
-fn solve_part1(
-    data: &Vec<(Vec<&str>, Vec<&str>)>,
-    food_to_possible_allergens: &HashMap<&str, HashSet<&str>>,
-) -> usize {
+fn solve_part1(data: &[(Vec<&str>, Vec<&str>)], food_to_possible_allergens: &MapOfSets) -> usize {
     let no_allergen_foods: HashSet<_> = food_to_possible_allergens
         .iter()
         .filter_map(|(&food, possible_allergens)| {
@@ -86,89 +90,75 @@ fn solve_part1(
     result
 }
 
-fn solve_part2(
-    allergen_to_food: &HashMap<&str, HashSet<&str>>,
-    food_to_allergens: &HashMap<&str, HashSet<&str>>,
-) -> String {
-    let mut allergen_in_possible_foods = allergen_to_food.clone();
-    let mut food_to_possible_allergens: HashMap<&str, HashSet<&str>> = food_to_allergens
+fn solve_part2<'a>(
+    allergen_to_food: &MapOfSets<'a>,
+    narrowing: Vec<NarrowingStep<'a>>,
+) -> Derivation<'a> {
+    let candidates: Candidates = allergen_to_food
         .iter()
-        .filter_map(|(&food, allergens)| {
-            if !allergens.is_empty() {
-                Some((food, allergens.clone()))
-            } else {
-                None
-            }
-        })
+        .map(|(&allergen, foods)| (allergen, foods.iter().copied().collect()))
         .collect();
 
-    let allergen_to_food: BTreeMap<&str, &str> = loop {
-        let mut converged = true;
-        for (&_allergen, possibilities) in &allergen_in_possible_foods {
-            assert!(possibilities.len() > 0);
-            if possibilities.len() > 1 {
-                converged = false;
-                break;
-            }
-        }
-
-        if converged {
-            break allergen_in_possible_foods
-                .iter()
-                .map(|(&allergen, foods)| (allergen, foods.iter().next().unwrap().clone()))
-                .collect();
-        }
+    let mut forced = vec![];
+    let outcome = solve_allergen_assignment(&candidates, &mut forced);
 
-        for (&allergen, possibilities) in &allergen_in_possible_foods {
-            if possibilities.len() == 1 {
-                let food_with_allergen = *possibilities.iter().next().unwrap();
-                assert!(food_to_possible_allergens
-                    .get(food_with_allergen)
-                    .unwrap()
-                    .contains(allergen));
+    Derivation {
+        narrowing,
+        forced,
+        outcome,
+    }
+}
 
-                food_to_possible_allergens
-                    .insert(food_with_allergen, vec![allergen].iter().cloned().collect());
-            }
-        }
+#[derive(Debug)]
+struct Options {
+    input_path: String,
+    explain: bool,
+    json_path: Option<String>,
+}
 
-        allergen_in_possible_foods = transpose_map_of_sets(&food_to_possible_allergens);
-        for (&food, possibilities) in &food_to_possible_allergens {
-            if possibilities.len() == 1 {
-                let allergen_of_food = *possibilities.iter().next().unwrap();
-                assert!(allergen_in_possible_foods
-                    .get(allergen_of_food)
-                    .unwrap()
-                    .contains(food));
-
-                allergen_in_possible_foods
-                    .insert(allergen_of_food, vec![food].iter().cloned().collect());
-            }
-        }
-        food_to_possible_allergens = transpose_map_of_sets(&allergen_in_possible_foods);
+fn parse_options(args: impl Iterator<Item = String>) -> Options {
+    let mut options = Options {
+        input_path: String::from(
+            "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day21/input.txt",
+        ),
+        explain: false,
+        json_path: None,
     };
 
-    println!("{:?}", allergen_to_food);
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--explain" => options.explain = true,
+            "--json" => options.json_path = Some(args.next().expect("missing JSON path")),
+            _ if arg.starts_with("--") => panic!("unrecognized option: {}", arg),
+            _ => options.input_path = arg,
+        }
+    }
 
-    allergen_to_food
-        .values()
-        .map(|&x| x.to_owned())
-        .collect::<Vec<_>>()
-        .join(",")
+    options
 }
 
 fn main() {
-    let contents = fs::read_to_string(
-        "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day21/input.txt",
-    )
-    .unwrap();
+    let options = parse_options(env::args().skip(1));
+    let contents = fs::read_to_string(&options.input_path).unwrap();
 
     let data: Vec<_> = contents.trim().split("\n").map(parse_line).collect();
-    let (allergen_to_food, food_to_possible_allergens) = assemble_initial_allergen_data(&data);
+    let mut narrowing = vec![];
+    let (allergen_to_food, food_to_possible_allergens) =
+        assemble_initial_allergen_data(&data, &mut narrowing);
 
     println!("{}", solve_part1(&data, &food_to_possible_allergens));
-    println!(
-        "{}",
-        solve_part2(&allergen_to_food, &food_to_possible_allergens)
-    );
+
+    let derivation = solve_part2(&allergen_to_food, narrowing);
+    if let AllergenAssignment::Unique(allergen_to_food) = &derivation.outcome {
+        println!("{:?}", allergen_to_food);
+    }
+    println!("{}", derivation.outcome);
+
+    if options.explain {
+        println!("{}", derivation.to_proof());
+    }
+    if let Some(json_path) = &options.json_path {
+        fs::write(json_path, derivation.to_json() + "\n").unwrap();
+    }
 }
@@ -0,0 +1,323 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+pub type Candidates<'a> = BTreeMap<&'a str, BTreeSet<&'a str>>;
+pub type Assignment<'a> = BTreeMap<&'a str, &'a str>;
+
+// Ambiguous inputs can have a huge number of valid assignments, don't list all of them.
+const MAX_REPORTED_ASSIGNMENTS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AllergenAssignment<'a> {
+    Unique(Assignment<'a>),
+    Ambiguous {
+        assignments: Vec<Assignment<'a>>,
+        truncated: bool,
+    },
+    // These allergens have fewer candidate foods between them than there are allergens,
+    // so some allergen can't be in any food (Hall's marriage theorem).
+    Impossible {
+        allergens: BTreeSet<&'a str>,
+        foods: BTreeSet<&'a str>,
+    },
+}
+
+// Foods listed alphabetically by the allergen they contain, as the puzzle answer wants.
+pub fn canonical_dangerous_ingredients(assignment: &Assignment) -> String {
+    assignment.values().copied().collect::<Vec<_>>().join(",")
+}
+
+impl<'a> fmt::Display for AllergenAssignment<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AllergenAssignment::Unique(assignment) => {
+                write!(f, "{}", canonical_dangerous_ingredients(assignment))
+            }
+            AllergenAssignment::Ambiguous {
+                assignments,
+                truncated,
+            } => {
+                write!(
+                    f,
+                    "ambiguous: {}{} valid assignments",
+                    if *truncated { "more than " } else { "" },
+                    assignments.len()
+                )?;
+                for assignment in assignments {
+                    write!(f, "\n{:?}", assignment)?;
+                }
+                Ok(())
+            }
+            AllergenAssignment::Impossible { allergens, foods } => {
+                let allergens: Vec<_> = allergens.iter().copied().collect();
+                let foods: Vec<_> = foods.iter().copied().collect();
+                write!(
+                    f,
+                    "impossible: the {} allergens {} can only be in the {} foods {}",
+                    allergens.len(),
+                    allergens.join(", "),
+                    foods.len(),
+                    if foods.is_empty() {
+                        String::from("(none)")
+                    } else {
+                        foods.join(", ")
+                    }
+                )
+            }
+        }
+    }
+}
+
+// An allergen whose only remaining candidate food was fixed, and the allergens that
+// no longer consider that food as a result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForcedPair<'a> {
+    pub allergen: &'a str,
+    pub food: &'a str,
+    pub eliminated_from: Vec<&'a str>,
+}
+
+// Fixes every allergen with a single candidate food, removing that food from the other
+// allergens' candidates, until nothing changes.
+pub fn propagate_unique_candidates<'a>(
+    candidates: &Candidates<'a>,
+    forced: &mut Vec<ForcedPair<'a>>,
+) -> Candidates<'a> {
+    let mut result = candidates.clone();
+    let mut fixed_foods: BTreeSet<&str> = BTreeSet::new();
+
+    loop {
+        let newly_fixed: Vec<(&str, &str)> = result
+            .iter()
+            .filter(|(_, foods)| foods.len() == 1)
+            .map(|(&allergen, foods)| (allergen, *foods.iter().next().unwrap()))
+            .filter(|(_, food)| !fixed_foods.contains(food))
+            .collect();
+        if newly_fixed.is_empty() {
+            break result;
+        }
+
+        for (allergen, food) in newly_fixed {
+            if !fixed_foods.insert(food) {
+                continue; // another allergen already claimed it, the matching reports this
+            }
+
+            let mut eliminated_from = vec![];
+            for (&other_allergen, foods) in result.iter_mut() {
+                if foods.len() > 1 && foods.remove(food) {
+                    eliminated_from.push(other_allergen);
+                }
+            }
+            forced.push(ForcedPair {
+                allergen,
+                food,
+                eliminated_from,
+            });
+        }
+    }
+}
+
+fn try_augment<'a>(
+    candidates: &Candidates<'a>,
+    allergen: &'a str,
+    visited_foods: &mut BTreeSet<&'a str>,
+    food_to_allergen: &mut BTreeMap<&'a str, &'a str>,
+) -> bool {
+    for &food in candidates[allergen].iter() {
+        if !visited_foods.insert(food) {
+            continue;
+        }
+
+        let augmented = match food_to_allergen.get(food) {
+            None => true,
+            Some(&current_allergen) => try_augment(
+                candidates,
+                current_allergen,
+                visited_foods,
+                food_to_allergen,
+            ),
+        };
+        if augmented {
+            food_to_allergen.insert(food, allergen);
+            return true;
+        }
+    }
+
+    false
+}
+
+// Kuhn's augmenting path algorithm. On failure, returns the allergens and foods reachable
+// from the first allergen that couldn't be matched: a set of allergens with too few foods.
+fn find_maximum_matching<'a>(
+    candidates: &Candidates<'a>,
+) -> Result<Assignment<'a>, (BTreeSet<&'a str>, BTreeSet<&'a str>)> {
+    let mut food_to_allergen: BTreeMap<&str, &str> = BTreeMap::new();
+
+    for &allergen in candidates.keys() {
+        let mut visited_foods = BTreeSet::new();
+        if !try_augment(
+            candidates,
+            allergen,
+            &mut visited_foods,
+            &mut food_to_allergen,
+        ) {
+            // Every visited food is matched, and leads to a distinct visited allergen.
+            let mut allergens: BTreeSet<&str> = visited_foods
+                .iter()
+                .map(|food| food_to_allergen[food])
+                .collect();
+            allergens.insert(allergen);
+            return Err((allergens, visited_foods));
+        }
+    }
+
+    Ok(food_to_allergen
+        .into_iter()
+        .map(|(food, allergen)| (allergen, food))
+        .collect())
+}
+
+fn enumerate_assignments<'a>(
+    candidates: &Candidates<'a>,
+    remaining_allergens: &[&'a str],
+    used_foods: &mut BTreeSet<&'a str>,
+    current: &mut Assignment<'a>,
+    results: &mut Vec<Assignment<'a>>,
+) {
+    if results.len() > MAX_REPORTED_ASSIGNMENTS {
+        return;
+    }
+
+    match remaining_allergens.split_first() {
+        None => results.push(current.clone()),
+        Some((&allergen, rest)) => {
+            for &food in candidates[allergen].iter() {
+                if used_foods.insert(food) {
+                    current.insert(allergen, food);
+                    enumerate_assignments(candidates, rest, used_foods, current, results);
+                    current.remove(allergen);
+                    used_foods.remove(food);
+                }
+            }
+        }
+    }
+}
+
+pub fn solve_allergen_assignment<'a>(
+    candidates: &Candidates<'a>,
+    forced: &mut Vec<ForcedPair<'a>>,
+) -> AllergenAssignment<'a> {
+    let candidates = propagate_unique_candidates(candidates, forced);
+
+    let matching = match find_maximum_matching(&candidates) {
+        Ok(matching) => matching,
+        Err((allergens, foods)) => return AllergenAssignment::Impossible { allergens, foods },
+    };
+    if candidates.values().all(|foods| foods.len() == 1) {
+        return AllergenAssignment::Unique(matching);
+    }
+
+    // Once propagation stalls, any allergen left with two candidates lies on an alternating
+    // path or cycle, so there are several assignments. List them, most constrained allergens
+    // first to keep the search small.
+    let mut allergens: Vec<&str> = candidates.keys().copied().collect();
+    allergens.sort_by_key(|allergen| candidates[allergen].len());
+
+    let mut assignments = vec![];
+    enumerate_assignments(
+        &candidates,
+        &allergens,
+        &mut BTreeSet::new(),
+        &mut BTreeMap::new(),
+        &mut assignments,
+    );
+
+    let truncated = assignments.len() > MAX_REPORTED_ASSIGNMENTS;
+    assignments.truncate(MAX_REPORTED_ASSIGNMENTS);
+    AllergenAssignment::Ambiguous {
+        assignments,
+        truncated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{solve_allergen_assignment, AllergenAssignment, Candidates};
+
+    fn make_candidates<'a>(data: &[(&'a str, &[&'a str])]) -> Candidates<'a> {
+        data.iter()
+            .map(|(allergen, foods)| (*allergen, foods.iter().copied().collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_unique_by_propagation() {
+        let candidates = make_candidates(&[
+            ("dairy", &["mxmxvkd"]),
+            ("fish", &["mxmxvkd", "sqjhc"]),
+            ("soy", &["sqjhc", "fvjkl"]),
+        ]);
+
+        let mut forced = vec![];
+        let result = solve_allergen_assignment(&candidates, &mut forced);
+        assert_eq!("mxmxvkd,sqjhc,fvjkl", result.to_string());
+
+        let forced_pairs: Vec<_> = forced
+            .iter()
+            .map(|pair| (pair.allergen, pair.food, pair.eliminated_from.clone()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("dairy", "mxmxvkd", vec!["fish"]),
+                ("fish", "sqjhc", vec!["soy"]),
+                ("soy", "fvjkl", vec![]),
+            ],
+            forced_pairs
+        );
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        // "c" can only be "z" since "a" and "b" use up "x" and "y", but those two can swap
+        let candidates = make_candidates(&[
+            ("a", &["x", "y"]),
+            ("b", &["x", "y"]),
+            ("c", &["x", "y", "z"]),
+        ]);
+
+        match solve_allergen_assignment(&candidates, &mut vec![]) {
+            AllergenAssignment::Ambiguous {
+                assignments,
+                truncated,
+            } => {
+                assert!(!truncated);
+                assert_eq!(2, assignments.len());
+                assert!(assignments.iter().all(|a| a["c"] == "z"));
+                assert_ne!(assignments[0]["a"], assignments[1]["a"]);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_impossible() {
+        let candidates = make_candidates(&[
+            ("dairy", &["mxmxvkd"]),
+            ("fish", &["mxmxvkd"]),
+            ("soy", &["sqjhc", "fvjkl"]),
+        ]);
+
+        match solve_allergen_assignment(&candidates, &mut vec![]) {
+            AllergenAssignment::Impossible { allergens, foods } => {
+                assert_eq!(
+                    vec!["dairy", "fish"],
+                    allergens.into_iter().collect::<Vec<_>>()
+                );
+                assert_eq!(vec!["mxmxvkd"], foods.into_iter().collect::<Vec<_>>());
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,399 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{
+    deck::HashedDeck,
+    trace::{SkipReason, TraceEvent, TraceSink},
+};
+
+// Combines each hash family across the decks separately, so the two halves of the key
+// stay independent and a repeat needs both 64-bit halves to collide.
+fn position_key(decks: &[HashedDeck]) -> (u64, u64) {
+    decks.iter().fold((0, 0), |(first, second), deck| {
+        let (first_hash, second_hash) = deck.hashes();
+        (
+            first
+                .wrapping_mul(0x2545_f491_4f6c_dd1d)
+                .wrapping_add(first_hash),
+            second
+                .wrapping_mul(0x9fb2_1c65_1e98_df25)
+                .wrapping_add(second_hash),
+        )
+    })
+}
+
+// The positions seen so far in a game. Normally only the keys are kept; checking also keeps
+// every deck, and panics if two different positions ever get the same key.
+enum SeenPositions {
+    Keys(HashSet<(u64, u64)>),
+    Checked(HashMap<(u64, u64), Vec<Vec<i64>>>),
+}
+
+impl SeenPositions {
+    fn new(check: bool) -> SeenPositions {
+        if check {
+            SeenPositions::Checked(HashMap::new())
+        } else {
+            SeenPositions::Keys(HashSet::new())
+        }
+    }
+
+    // Returns false if the position was already seen.
+    fn insert(&mut self, decks: &[HashedDeck]) -> bool {
+        let key = position_key(decks);
+        match self {
+            SeenPositions::Keys(keys) => keys.insert(key),
+            SeenPositions::Checked(positions) => {
+                let cards: Vec<Vec<i64>> = decks.iter().map(|deck| deck.iter().collect()).collect();
+                match positions.get(&key) {
+                    Some(seen) => {
+                        assert_eq!(seen, &cards, "two positions share the key {:?}", key);
+                        false
+                    }
+                    None => {
+                        positions.insert(key, cards);
+                        true
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GameStats {
+    pub rounds: usize,       // in the outermost game
+    pub total_rounds: usize, // including every sub-game
+    pub games: usize,        // including the outermost game
+    pub max_depth: usize,
+    pub ended_by_loop: bool, // the outermost game ended by the loop rule
+}
+
+// Combat for any number of players, numbered from 1 in input order. The rules reduce to
+// the puzzle's when there are two players:
+// - Every player with cards left draws their top card. Players without cards are out.
+// - In a recursive game, if every player who drew has at least as many cards left as the
+//   value they drew, the round is decided by a sub-game between those players, each with
+//   that many cards from the top of their deck. Otherwise, the highest card wins, and
+//   equal highest cards are won by the lowest-numbered player among them.
+// - The round winner puts their own card on the bottom of their deck first, then the others
+//   from highest to lowest, equal cards in player order.
+// - If all decks are the same as in an earlier round of the same game, the lowest-numbered
+//   player still in the game wins it. The puzzle only has this rule for recursive games,
+//   but plain games can also loop forever, so they use it too.
+// - The game ends when only one player has cards left.
+pub struct Combat<'a> {
+    trace: &'a mut dyn TraceSink,
+    recursive: bool,
+    shortcuts: bool, // skip sub-games whose winner is already known
+    check_positions: bool,
+    games_started: usize,
+    stats: GameStats,
+    sub_game_winners: HashMap<Vec<Vec<i64>>, usize>, // keyed by the starting decks
+}
+
+impl<'a> Combat<'a> {
+    pub fn new(trace: &'a mut dyn TraceSink, recursive: bool, shortcuts: bool) -> Combat<'a> {
+        Combat {
+            trace,
+            recursive,
+            shortcuts,
+            check_positions: false,
+            games_started: 0,
+            stats: GameStats::default(),
+            sub_game_winners: HashMap::new(),
+        }
+    }
+
+    // Keeps every position's decks to check that no two of them share a key. This is slow
+    // and uses a lot of memory, so it's only meant for debugging.
+    pub fn with_position_check(mut self) -> Combat<'a> {
+        self.check_positions = true;
+        self
+    }
+
+    // Returns the winning player's number and final deck.
+    pub fn play(&mut self, decks: &[VecDeque<i64>]) -> (usize, VecDeque<i64>) {
+        assert!(decks.len() >= 2, "Combat needs at least two players");
+        let decks = decks
+            .iter()
+            .map(|deck| HashedDeck::new(deck.iter().copied()))
+            .collect();
+
+        let (winner, mut decks) = self.play_game(decks, 1);
+        (winner, decks.swap_remove(winner - 1).into_cards())
+    }
+
+    // Counts for every game played so far, skipped sub-games excluded.
+    pub fn stats(&self) -> GameStats {
+        GameStats {
+            games: self.games_started,
+            ..self.stats
+        }
+    }
+
+    fn decide_sub_game(
+        &mut self,
+        subdecks: Vec<Vec<i64>>,
+        game: usize,
+        depth: usize,
+        round: usize,
+    ) -> usize {
+        if self.shortcuts {
+            // No sub-game can ever recurse on a card at least as large as the number of cards
+            // in play, since its owner can never hold that many other cards. With distinct
+            // cards, the highest one always qualifies, so its owner is never knocked out.
+            // If they're also the lowest-numbered player, they win either by taking every
+            // card or by the loop rule.
+            let card_count: usize = subdecks.iter().map(|deck| deck.len()).sum();
+            let highest_card = subdecks.iter().flatten().max().copied();
+            let first_player = subdecks.iter().position(|deck| !deck.is_empty()).unwrap();
+            let skipped = if highest_card >= Some(card_count as i64)
+                && subdecks[first_player].iter().max().copied() == highest_card
+            {
+                Some((first_player + 1, SkipReason::HighestCard))
+            } else {
+                self.sub_game_winners
+                    .get(&subdecks)
+                    .map(|&winner| (winner, SkipReason::SeenBefore))
+            };
+
+            if let Some((winner, reason)) = skipped {
+                if self.trace.is_enabled() {
+                    self.trace.record(TraceEvent::SubGameSkipped {
+                        game,
+                        depth,
+                        round,
+                        winner,
+                        reason,
+                    });
+                }
+                return winner;
+            }
+        }
+
+        if self.trace.is_enabled() {
+            self.trace.record(TraceEvent::SubGameSpawned {
+                game,
+                depth,
+                round,
+                sub_game: self.games_started + 1,
+            });
+        }
+        let decks = subdecks
+            .iter()
+            .map(|deck| HashedDeck::new(deck.iter().copied()))
+            .collect();
+        let (winner, _) = self.play_game(decks, depth + 1);
+        if self.shortcuts {
+            self.sub_game_winners.insert(subdecks, winner);
+        }
+        winner
+    }
+
+    fn play_game(&mut self, mut decks: Vec<HashedDeck>, depth: usize) -> (usize, Vec<HashedDeck>) {
+        self.games_started += 1;
+        let game = self.games_started;
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+        if self.trace.is_enabled() {
+            self.trace.record(TraceEvent::GameStarted { game, depth });
+        }
+
+        let mut inf_game_prevention = SeenPositions::new(self.check_positions);
+        let mut round: usize = 0;
+
+        let winning_player_id = loop {
+            let active_players: Vec<usize> = (0..decks.len())
+                .filter(|&player| !decks[player].is_empty())
+                .collect();
+            if active_players.len() == 1 {
+                break active_players[0] + 1;
+            }
+
+            round += 1;
+            if !inf_game_prevention.insert(&decks) {
+                self.stats.ended_by_loop |= depth == 1;
+                if self.trace.is_enabled() {
+                    self.trace.record(TraceEvent::LoopDetected {
+                        game,
+                        depth,
+                        round,
+                        winner: active_players[0] + 1,
+                    });
+                }
+                break active_players[0] + 1;
+            }
+
+            if self.trace.is_enabled() {
+                self.trace.record(TraceEvent::RoundStarted {
+                    game,
+                    depth,
+                    round,
+                    decks: decks.iter().map(|deck| deck.iter().collect()).collect(),
+                    cards: decks.iter().map(|deck| deck.front()).collect(),
+                });
+            }
+
+            let mut drawn: Vec<(usize, i64)> = active_players
+                .iter()
+                .map(|&player| (player, decks[player].pop_front().unwrap()))
+                .collect();
+
+            let recurse = self.recursive
+                && drawn
+                    .iter()
+                    .all(|&(player, card)| decks[player].len() as i64 >= card);
+            let games_before = self.games_started;
+            let round_winner = if recurse {
+                let mut subdecks = vec![vec![]; decks.len()];
+                for &(player, card) in drawn.iter() {
+                    subdecks[player] = decks[player].iter().take(card as usize).collect();
+                }
+                self.decide_sub_game(subdecks, game, depth, round) - 1
+            } else {
+                // insufficient deck size, play as normal
+                let &(player, _) = drawn
+                    .iter()
+                    .max_by_key(|&&(player, card)| (card, std::cmp::Reverse(player)))
+                    .unwrap();
+                player
+            };
+
+            drawn.sort_by_key(|&(player, card)| {
+                (player != round_winner, std::cmp::Reverse(card), player)
+            });
+            for (_, card) in drawn {
+                decks[round_winner].push_back(card);
+            }
+
+            self.stats.total_rounds += 1;
+            if depth == 1 {
+                self.stats.rounds += 1;
+            }
+
+            if self.trace.is_enabled() {
+                self.trace.record(TraceEvent::RoundWon {
+                    game,
+                    depth,
+                    round,
+                    winner: round_winner + 1,
+                    by_sub_game: self.games_started > games_before,
+                });
+            }
+        };
+
+        if self.trace.is_enabled() {
+            self.trace.record(TraceEvent::GameWon {
+                game,
+                depth,
+                winner: winning_player_id,
+                decks: decks.iter().map(|deck| deck.iter().collect()).collect(),
+            });
+        }
+
+        (winning_player_id, decks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::{position_key, Combat};
+    use crate::{deck::HashedDeck, trace::NoTrace};
+
+    fn make_decks(decks: &[&[i64]]) -> Vec<VecDeque<i64>> {
+        decks
+            .iter()
+            .map(|deck| deck.iter().copied().collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_three_player_plain_game() {
+        // 5 beats 3 and 1, then 6 beats 4 and 2 and knocks out player 1,
+        // and player 2's high cards win every round after that
+        let decks = make_decks(&[&[1, 2], &[3, 6], &[5, 4]]);
+        assert_eq!(
+            (2, VecDeque::from(vec![6, 5, 4, 3, 2, 1])),
+            Combat::new(&mut NoTrace, false, false).play(&decks)
+        );
+
+        // the winner's card goes first, then the others from highest to lowest
+        let decks = make_decks(&[&[1], &[3], &[5]]);
+        assert_eq!(
+            (3, VecDeque::from(vec![5, 3, 1])),
+            Combat::new(&mut NoTrace, false, false).play(&decks)
+        );
+    }
+
+    #[test]
+    fn test_ties_go_to_the_lowest_numbered_player() {
+        let decks = make_decks(&[&[2], &[7], &[7]]);
+        assert_eq!(
+            (2, VecDeque::from(vec![7, 7, 2])),
+            Combat::new(&mut NoTrace, false, false).play(&decks)
+        );
+    }
+
+    #[test]
+    fn test_plain_loops_are_won_by_the_first_player() {
+        // the puzzle's looping example, which never ends without the loop rule
+        let decks = make_decks(&[&[43, 19], &[2, 29, 14]]);
+        let (winner, _) = Combat::new(&mut NoTrace, false, false).play(&decks);
+        assert_eq!(1, winner);
+    }
+
+    #[test]
+    fn test_stats() {
+        let decks = crate::parse_decks(include_str!("../sample_input.txt"));
+        let mut no_trace = NoTrace;
+
+        let mut plain = Combat::new(&mut no_trace, false, false);
+        plain.play(&decks);
+        assert_eq!(29, plain.stats().rounds);
+        assert_eq!(1, plain.stats().max_depth);
+
+        let mut recursive = Combat::new(&mut no_trace, true, false);
+        recursive.play(&decks);
+        let stats = recursive.stats();
+        assert_eq!((17, 5, 3), (stats.rounds, stats.games, stats.max_depth));
+        assert!(stats.total_rounds > stats.rounds && !stats.ended_by_loop);
+
+        let looping = make_decks(&[&[43, 19], &[2, 29, 14]]);
+        let mut recursive = Combat::new(&mut no_trace, true, false);
+        recursive.play(&looping);
+        assert!(recursive.stats().ended_by_loop);
+    }
+
+    #[test]
+    fn test_position_keys_tell_positions_apart() {
+        let position = |decks: &[&[i64]]| {
+            let decks: Vec<HashedDeck> = decks
+                .iter()
+                .map(|deck| HashedDeck::new(deck.iter().copied()))
+                .collect();
+            position_key(&decks)
+        };
+
+        let key = position(&[&[1, 2], &[3]]);
+        assert_eq!(key, position(&[&[1, 2], &[3]]));
+        assert_ne!(key, position(&[&[2, 1], &[3]]));
+        assert_ne!(key, position(&[&[3], &[1, 2]]));
+        assert_ne!(key, position(&[&[1], &[2, 3]]));
+        assert_ne!(key.0, key.1);
+    }
+
+    #[test]
+    fn test_three_player_recursive_game() {
+        let decks = make_decks(&[&[2, 9, 1, 11], &[1, 3, 12, 8], &[1, 10, 4, 7, 6, 5]]);
+
+        let without_shortcuts = Combat::new(&mut NoTrace, true, false)
+            .with_position_check()
+            .play(&decks);
+        let with_shortcuts = Combat::new(&mut NoTrace, true, true)
+            .with_position_check()
+            .play(&decks);
+        assert_eq!(without_shortcuts, with_shortcuts);
+        assert_eq!(14, with_shortcuts.1.len());
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::VecDeque;
+
+// Newton's iteration for the inverse of an odd number modulo 2^64: each step doubles
+// the number of correct low bits, and x = a is already correct in the lowest three.
+const fn modular_inverse(a: u64) -> u64 {
+    let mut x = a;
+    let mut step = 0;
+    while step < 5 {
+        x = x.wrapping_mul(2u64.wrapping_sub(a.wrapping_mul(x)));
+        step += 1;
+    }
+    x
+}
+
+// sum(mix(card_i) * base^i) over the cards from the top, modulo 2^64, where mix(card) is
+// (card + 1) * multiplier. The base is odd, so it has a multiplicative inverse.
+#[derive(Debug, Clone, Copy)]
+struct PolynomialHash {
+    base: u64,
+    base_inverse: u64,
+    multiplier: u64,
+    hash: u64,
+    next_power: u64, // base^len
+}
+
+impl PolynomialHash {
+    const fn new(base: u64, multiplier: u64) -> PolynomialHash {
+        PolynomialHash {
+            base,
+            base_inverse: modular_inverse(base),
+            multiplier,
+            hash: 0,
+            next_power: 1,
+        }
+    }
+
+    fn mix_card(&self, card: i64) -> u64 {
+        // keep every card, including zero, from contributing nothing to the hash
+        (card as u64).wrapping_add(1).wrapping_mul(self.multiplier)
+    }
+
+    fn push_back(&mut self, card: i64) {
+        self.hash = self
+            .hash
+            .wrapping_add(self.mix_card(card).wrapping_mul(self.next_power));
+        self.next_power = self.next_power.wrapping_mul(self.base);
+    }
+
+    fn pop_front(&mut self, card: i64) {
+        // every remaining card moves one place up, dividing its term by the base
+        self.hash = self
+            .hash
+            .wrapping_sub(self.mix_card(card))
+            .wrapping_mul(self.base_inverse);
+        self.next_power = self.next_power.wrapping_mul(self.base_inverse);
+    }
+}
+
+// Two hashes with unrelated bases and multipliers, so a repeat needs both to collide.
+const EMPTY_HASHES: [PolynomialHash; 2] = [
+    PolynomialHash::new(0x9e37_79b9_7f4a_7c15, 0xff51_afd7_ed55_8ccd),
+    PolynomialHash::new(0xc2b2_ae3d_27d4_eb4f, 0xc4ce_b9fe_1a85_ec53),
+];
+
+// A deck that keeps the hashes of its cards up to date as they are drawn and won, so that
+// repeated positions can be detected without copying the deck every round.
+#[derive(Debug, Clone)]
+pub struct HashedDeck {
+    cards: VecDeque<i64>,
+    hashes: [PolynomialHash; 2],
+}
+
+impl HashedDeck {
+    pub fn new(cards: impl IntoIterator<Item = i64>) -> HashedDeck {
+        let mut deck = HashedDeck {
+            cards: VecDeque::new(),
+            hashes: EMPTY_HASHES,
+        };
+        for card in cards {
+            deck.push_back(card);
+        }
+        deck
+    }
+
+    pub fn hashes(&self) -> (u64, u64) {
+        (self.hashes[0].hash, self.hashes[1].hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    pub fn front(&self) -> Option<i64> {
+        self.cards.front().copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = i64> + '_ {
+        self.cards.iter().copied()
+    }
+
+    pub fn push_back(&mut self, card: i64) {
+        for hash in self.hashes.iter_mut() {
+            hash.push_back(card);
+        }
+        self.cards.push_back(card);
+    }
+
+    pub fn pop_front(&mut self) -> Option<i64> {
+        let card = self.cards.pop_front()?;
+        for hash in self.hashes.iter_mut() {
+            hash.pop_front(card);
+        }
+        Some(card)
+    }
+
+    pub fn into_cards(self) -> VecDeque<i64> {
+        self.cards
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HashedDeck, EMPTY_HASHES};
+
+    #[test]
+    fn test_base_inverses() {
+        for hash in EMPTY_HASHES.iter() {
+            assert_eq!(1, hash.base.wrapping_mul(hash.base_inverse));
+        }
+    }
+
+    #[test]
+    fn test_hash_depends_only_on_cards() {
+        let mut deck = HashedDeck::new(vec![9, 2, 6, 3, 1]);
+        assert_eq!(Some(9), deck.pop_front());
+        assert_eq!(Some(2), deck.pop_front());
+        deck.push_back(9);
+        deck.push_back(2);
+
+        let fresh = HashedDeck::new(vec![6, 3, 1, 9, 2]);
+        assert_eq!(fresh.hashes(), deck.hashes());
+        assert_ne!(HashedDeck::new(vec![3, 6, 1, 9, 2]).hashes(), deck.hashes());
+        assert_ne!(
+            HashedDeck::new(vec![0]).hashes(),
+            HashedDeck::new(vec![]).hashes()
+        );
+
+        while deck.pop_front().is_some() {}
+        assert_eq!(HashedDeck::new(vec![]).hashes(), deck.hashes());
+    }
+}
@@ -1,29 +1,180 @@
 use std::{
-    collections::{HashSet, VecDeque},
-    fs,
+    collections::VecDeque,
+    env,
+    fs::{self, File},
+    io::BufWriter,
+    thread,
 };
 
-fn main() {
-    let contents = fs::read_to_string(
-        "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day22/input.txt",
-    )
-    .unwrap();
+mod combat;
+mod deck;
+mod simulation;
+mod tournament;
+mod trace;
+
+use combat::Combat;
+use simulation::{describe_results, simulate, SimulationOptions};
+use tournament::play_tournament;
+use trace::{NoTrace, TraceFormat, TraceSink, TraceWriter};
+
+#[derive(Debug)]
+struct Options {
+    input_path: String,
+    trace_path: Option<String>,
+    trace_format: TraceFormat,
+    shortcuts: bool,
+    check_positions: bool,
+    tournament: bool,
+    simulate: bool,
+    simulation_options: SimulationOptions,
+}
+
+fn parse_options(args: impl Iterator<Item = String>) -> Options {
+    let mut options = Options {
+        input_path: String::from(
+            "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day22/input.txt",
+        ),
+        trace_path: None,
+        trace_format: TraceFormat::Text,
+        shortcuts: true,
+        check_positions: false,
+        tournament: false,
+        simulate: false,
+        simulation_options: SimulationOptions {
+            games: 0,
+            players: 2,
+            deck_size: 25,
+            seed: 0,
+            threads: thread::available_parallelism().map_or(1, |count| count.get()),
+            shortcuts: true,
+        },
+    };
 
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--trace" => options.trace_path = Some(args.next().expect("missing trace path")),
+            "--trace-format" => {
+                options.trace_format =
+                    TraceFormat::parse(&args.next().expect("missing trace format"))
+            }
+            "--no-shortcuts" => options.shortcuts = false,
+            "--check-positions" => options.check_positions = true,
+            "--tournament" => options.tournament = true,
+            "--simulate" => {
+                options.simulate = true;
+                options.simulation_options.games =
+                    args.next().expect("missing game count").parse().unwrap()
+            }
+            "--players" => {
+                options.simulation_options.players =
+                    args.next().expect("missing player count").parse().unwrap()
+            }
+            "--deck-size" => {
+                options.simulation_options.deck_size =
+                    args.next().expect("missing deck size").parse().unwrap()
+            }
+            "--seed" => {
+                options.simulation_options.seed =
+                    args.next().expect("missing seed").parse().unwrap()
+            }
+            "--threads" => {
+                options.simulation_options.threads =
+                    args.next().expect("missing thread count").parse().unwrap()
+            }
+            _ if arg.starts_with("--") => panic!("unrecognized option: {}", arg),
+            _ => options.input_path = arg,
+        }
+    }
+
+    options.simulation_options.shortcuts = options.shortcuts;
+    options
+}
+
+fn parse_decks(contents: &str) -> Vec<VecDeque<i64>> {
     let players: Vec<Vec<_>> = contents
         .trim()
         .split("\n\n")
         .map(|x| x.trim().split("\n").collect())
         .collect();
-    assert_eq!(players[0][0], "Player 1:");
-    assert_eq!(players[1][0], "Player 2:");
+    assert!(players.len() >= 2);
+    for (index, player) in players.iter().enumerate() {
+        assert_eq!(player[0], format!("Player {}:", index + 1));
+    }
 
-    let player_decks: Vec<_> = players.iter().map(parse_player).collect();
+    players.iter().map(|player| parse_player(player)).collect()
+}
 
-    println!("{}", solve_part1(&player_decks[0], &player_decks[1]));
-    println!("{}", solve_part2(&player_decks[0], &player_decks[1]));
+fn run_simulation(options: &SimulationOptions) {
+    let setup = format!(
+        "{} games of {} players with {} cards each",
+        options.games, options.players, options.deck_size
+    );
+    let plain_results = simulate(options, false);
+    println!(
+        "{}",
+        describe_results(&format!("Combat, {}", setup), &plain_results, false)
+    );
+    let recursive_results = simulate(options, true);
+    println!(
+        "{}",
+        describe_results(
+            &format!("Recursive Combat, {}", setup),
+            &recursive_results,
+            true
+        )
+    );
 }
 
-fn parse_player(player: &Vec<&str>) -> VecDeque<i64> {
+fn main() {
+    let options = parse_options(env::args().skip(1));
+    if options.simulate {
+        run_simulation(&options.simulation_options);
+        return;
+    }
+
+    let contents = fs::read_to_string(&options.input_path).unwrap();
+    let player_decks = parse_decks(&contents);
+
+    println!("{}", solve_part1(&player_decks));
+
+    match &options.trace_path {
+        None => println!(
+            "{}",
+            solve_part2(
+                &player_decks,
+                &mut NoTrace,
+                options.shortcuts,
+                options.check_positions
+            )
+        ),
+        Some(trace_path) => {
+            let output = BufWriter::new(File::create(trace_path).unwrap());
+            let mut trace = TraceWriter::new(output, options.trace_format);
+            println!(
+                "{}",
+                solve_part2(
+                    &player_decks,
+                    &mut trace,
+                    options.shortcuts,
+                    options.check_positions
+                )
+            );
+            trace.finish().unwrap();
+        }
+    }
+
+    if options.tournament {
+        for standing in play_tournament(&player_decks, options.shortcuts) {
+            println!(
+                "Player {}: {} plain wins, {} recursive wins",
+                standing.player, standing.plain_wins, standing.recursive_wins
+            );
+        }
+    }
+}
+
+fn parse_player(player: &[&str]) -> VecDeque<i64> {
     player[1..player.len()]
         .iter()
         .map(|&x| x.parse().unwrap())
@@ -39,101 +190,46 @@ fn score_deck(winning_deck: &VecDeque<i64>) -> i64 {
     total_score
 }
 
-fn solve_part1(first_player: &VecDeque<i64>, second_player: &VecDeque<i64>) -> i64 {
-    let mut player_a = first_player.clone();
-    let mut player_b = second_player.clone();
-
-    let winning_deck = loop {
-        match (player_a.pop_front(), player_b.pop_front()) {
-            (Some(a), Some(b)) => {
-                if a > b {
-                    player_a.push_back(a);
-                    player_a.push_back(b);
-                } else if a < b {
-                    player_b.push_back(b);
-                    player_b.push_back(a);
-                } else {
-                    unreachable!();
-                }
-            }
-            (Some(a), None) => {
-                player_a.push_front(a);
-                break &player_a;
-            }
-            (None, Some(b)) => {
-                player_b.push_front(b);
-                break &player_b;
-            }
-            (None, None) => unreachable!(),
-        }
-    };
+fn solve_part1(decks: &[VecDeque<i64>]) -> i64 {
+    let (_, winning_deck) = Combat::new(&mut NoTrace, false, false).play(decks);
 
-    score_deck(winning_deck)
+    score_deck(&winning_deck)
 }
 
-fn solve_part2(first_player: &VecDeque<i64>, second_player: &VecDeque<i64>) -> i64 {
-    let player_a = first_player.clone();
-    let player_b = second_player.clone();
-
-    let (_, winning_deck) = play_game(player_a, player_b);
+fn solve_part2(
+    decks: &[VecDeque<i64>],
+    trace: &mut dyn TraceSink,
+    shortcuts: bool,
+    check_positions: bool,
+) -> i64 {
+    let mut combat = Combat::new(trace, true, shortcuts);
+    if check_positions {
+        combat = combat.with_position_check();
+    }
+    let (_, winning_deck) = combat.play(decks);
 
     score_deck(&winning_deck)
 }
 
-fn play_game(mut player_a: VecDeque<i64>, mut player_b: VecDeque<i64>) -> (usize, VecDeque<i64>) {
-    let mut inf_game_prevention: HashSet<(Vec<i64>, Vec<i64>)> = HashSet::new();
-
-    let (winning_player_id, winning_deck) = loop {
-        let player_a_deck: Vec<_> = player_a.iter().cloned().collect();
-        let player_b_deck: Vec<_> = player_b.iter().cloned().collect();
-
-        if !inf_game_prevention.insert((player_a_deck, player_b_deck)) {
-            break (1usize, player_a);
+#[cfg(test)]
+mod tests {
+    use numtheory::Rng;
+
+    use crate::{parse_decks, simulation::deal_decks, solve_part1, solve_part2, trace::NoTrace};
+
+    #[test]
+    fn test_shortcuts_do_not_change_the_outcome() {
+        let decks = parse_decks(include_str!("../sample_input.txt"));
+        assert_eq!(306, solve_part1(&decks));
+        assert_eq!(291, solve_part2(&decks, &mut NoTrace, true, true));
+
+        let mut rng = Rng::new(22);
+        for _ in 0..20 {
+            let decks = deal_decks(&mut rng, 2, 10);
+            assert_eq!(
+                solve_part2(&decks, &mut NoTrace, false, true),
+                solve_part2(&decks, &mut NoTrace, true, true)
+            );
         }
-
-        match (player_a.pop_front(), player_b.pop_front()) {
-            (Some(a), Some(b)) => {
-                if player_a.len() as i64 >= a && player_b.len() as i64 >= b {
-                    let player_a_subdeck: VecDeque<i64> =
-                        player_a.iter().cloned().take(a as usize).collect();
-                    let player_b_subdeck: VecDeque<i64> =
-                        player_b.iter().cloned().take(b as usize).collect();
-
-                    match play_game(player_a_subdeck, player_b_subdeck) {
-                        (1usize, _) => {
-                            player_a.push_back(a);
-                            player_a.push_back(b);
-                        }
-                        (2usize, _) => {
-                            player_b.push_back(b);
-                            player_b.push_back(a);
-                        }
-                        _ => unreachable!(),
-                    }
-                } else {
-                    // insufficient deck size, play as normal
-                    if a > b {
-                        player_a.push_back(a);
-                        player_a.push_back(b);
-                    } else if a < b {
-                        player_b.push_back(b);
-                        player_b.push_back(a);
-                    } else {
-                        unreachable!();
-                    }
-                }
-            }
-            (Some(a), None) => {
-                player_a.push_front(a);
-                break (1usize, player_a);
-            }
-            (None, Some(b)) => {
-                player_b.push_front(b);
-                break (2usize, player_b);
-            }
-            (None, None) => unreachable!(),
-        }
-    };
-
-    (winning_player_id, winning_deck.clone())
+    }
 }
@@ -0,0 +1,198 @@
+use std::{collections::VecDeque, thread};
+
+use numtheory::Rng;
+
+use crate::{
+    combat::{Combat, GameStats},
+    trace::NoTrace,
+};
+
+// Shuffles the cards 1 through players * deck_size and deals them out, like the puzzle's decks.
+pub fn deal_decks(rng: &mut Rng, players: usize, deck_size: usize) -> Vec<VecDeque<i64>> {
+    let mut cards: Vec<i64> = (1..=(players * deck_size) as i64).collect();
+
+    // Fisher-Yates shuffle
+    for index in (1..cards.len()).rev() {
+        let other = rng.index(index + 1);
+        cards.swap(index, other);
+    }
+
+    cards
+        .chunks(deck_size)
+        .map(|deck| deck.iter().copied().collect())
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SimulationOptions {
+    pub games: usize,
+    pub players: usize,
+    pub deck_size: usize,
+    pub seed: u64,
+    pub threads: usize,
+    pub shortcuts: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GameResult {
+    pub winner: usize,
+    pub stats: GameStats,
+}
+
+// Each game gets its own generator, so the results don't depend on the number of threads.
+fn play_random_game(options: &SimulationOptions, index: usize, recursive: bool) -> GameResult {
+    let mut rng = Rng::for_stream(options.seed, index as u64);
+    let decks = deal_decks(&mut rng, options.players, options.deck_size);
+
+    let mut no_trace = NoTrace;
+    let mut combat = Combat::new(&mut no_trace, recursive, options.shortcuts);
+    let (winner, _) = combat.play(&decks);
+    GameResult {
+        winner,
+        stats: combat.stats(),
+    }
+}
+
+pub fn simulate(options: &SimulationOptions, recursive: bool) -> Vec<GameResult> {
+    let threads = options.threads.clamp(1, options.games.max(1));
+    let games_per_thread = options.games.div_ceil(threads);
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|thread_index| {
+                let start = (thread_index * games_per_thread).min(options.games);
+                let end = (start + games_per_thread).min(options.games);
+                scope.spawn(move || {
+                    (start..end)
+                        .map(|index| play_random_game(options, index, recursive))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    })
+}
+
+// Summarizes a sample by its mean and a few percentiles.
+fn describe_distribution(values: &[usize]) -> String {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    if sorted.is_empty() {
+        return String::from("(no games)");
+    }
+
+    let percentile = |percent: usize| sorted[(sorted.len() - 1) * percent / 100];
+    let mean = sorted.iter().sum::<usize>() as f64 / sorted.len() as f64;
+    format!(
+        "min {}, 10% {}, median {}, 90% {}, 99% {}, max {}, mean {:.1}",
+        sorted[0],
+        percentile(10),
+        percentile(50),
+        percentile(90),
+        percentile(99),
+        sorted[sorted.len() - 1],
+        mean
+    )
+}
+
+fn describe_rate(count: usize, total: usize) -> String {
+    format!(
+        "{} of {} ({:.1}%)",
+        count,
+        total,
+        100.0 * count as f64 / total.max(1) as f64
+    )
+}
+
+pub fn describe_results(title: &str, results: &[GameResult], recursive: bool) -> String {
+    let collect = |stat: fn(&GameStats) -> usize| -> Vec<usize> {
+        results.iter().map(|result| stat(&result.stats)).collect()
+    };
+
+    let mut lines = vec![format!("{}:", title)];
+    lines.push(format!(
+        "  rounds: {}",
+        describe_distribution(&collect(|stats| stats.rounds))
+    ));
+    if recursive {
+        lines.push(format!(
+            "  rounds including sub-games: {}",
+            describe_distribution(&collect(|stats| stats.total_rounds))
+        ));
+        lines.push(format!(
+            "  sub-games: {}",
+            describe_distribution(&collect(|stats| stats.games - 1))
+        ));
+        lines.push(format!(
+            "  recursion depth: {}",
+            describe_distribution(&collect(|stats| stats.max_depth))
+        ));
+    }
+
+    let loops = results
+        .iter()
+        .filter(|result| result.stats.ended_by_loop)
+        .count();
+    let first_player_wins = results.iter().filter(|result| result.winner == 1).count();
+    lines.push(format!(
+        "  ended by the loop rule: {}",
+        describe_rate(loops, results.len())
+    ));
+    lines.push(format!(
+        "  won by player 1: {}",
+        describe_rate(first_player_wins, results.len())
+    ));
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use numtheory::Rng;
+
+    use super::{deal_decks, describe_distribution, simulate, SimulationOptions};
+
+    #[test]
+    fn test_deal_decks() {
+        let decks = deal_decks(&mut Rng::new(7), 3, 4);
+        assert_eq!(3, decks.len());
+        assert!(decks.iter().all(|deck| deck.len() == 4));
+
+        let mut cards: Vec<i64> = decks.iter().flatten().copied().collect();
+        cards.sort_unstable();
+        assert_eq!((1..=12).collect::<Vec<_>>(), cards);
+    }
+
+    #[test]
+    fn test_results_do_not_depend_on_threads() {
+        let mut options = SimulationOptions {
+            games: 30,
+            players: 2,
+            deck_size: 8,
+            seed: 1,
+            threads: 1,
+            shortcuts: true,
+        };
+        let single = simulate(&options, true);
+        options.threads = 4;
+        let parallel = simulate(&options, true);
+
+        assert_eq!(30, parallel.len());
+        for (a, b) in single.iter().zip(parallel.iter()) {
+            assert_eq!((a.winner, a.stats), (b.winner, b.stats));
+        }
+    }
+
+    #[test]
+    fn test_describe_distribution() {
+        let values: Vec<usize> = (1..=100).collect();
+        assert_eq!(
+            "min 1, 10% 10, median 50, 90% 90, 99% 99, max 100, mean 50.5",
+            describe_distribution(&values)
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::VecDeque;
+
+use crate::{combat::Combat, trace::NoTrace};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    pub player: usize,
+    pub plain_wins: usize,
+    pub recursive_wins: usize,
+}
+
+// Every deck plays every other one head to head, in both seating orders since
+// player 1 has the edge when a game loops, with both plain and recursive rules.
+pub fn play_tournament(decks: &[VecDeque<i64>], shortcuts: bool) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = (1..=decks.len())
+        .map(|player| Standing {
+            player,
+            plain_wins: 0,
+            recursive_wins: 0,
+        })
+        .collect();
+
+    for first in 0..decks.len() {
+        for second in 0..decks.len() {
+            if first == second {
+                continue;
+            }
+
+            let pairing = vec![decks[first].clone(), decks[second].clone()];
+            let seats = [first, second];
+
+            let (winner, _) = Combat::new(&mut NoTrace, false, false).play(&pairing);
+            standings[seats[winner - 1]].plain_wins += 1;
+
+            let (winner, _) = Combat::new(&mut NoTrace, true, shortcuts).play(&pairing);
+            standings[seats[winner - 1]].recursive_wins += 1;
+        }
+    }
+
+    standings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{play_tournament, Standing};
+    use crate::parse_decks;
+
+    #[test]
+    fn test_sample_tournament() {
+        let decks = parse_decks(include_str!("../sample_input.txt"));
+        let standings = play_tournament(&decks, true);
+
+        // player 2 wins the sample both ways, whichever seat they're in
+        assert_eq!(
+            vec![
+                Standing {
+                    player: 1,
+                    plain_wins: 0,
+                    recursive_wins: 0,
+                },
+                Standing {
+                    player: 2,
+                    plain_wins: 2,
+                    recursive_wins: 2,
+                },
+            ],
+            standings
+        );
+    }
+}
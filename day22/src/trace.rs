@@ -0,0 +1,456 @@
+use std::io::{self, Write};
+
+// What happened during a game of Recursive Combat. Games are numbered in the order they
+// start, like the puzzle does, and depth is 1 for the outermost game. Players are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceEvent {
+    GameStarted {
+        game: usize,
+        depth: usize,
+    },
+    RoundStarted {
+        game: usize,
+        depth: usize,
+        round: usize,
+        decks: Vec<Vec<i64>>,    // before the cards are drawn
+        cards: Vec<Option<i64>>, // None for players who are out
+    },
+    SubGameSpawned {
+        game: usize,
+        depth: usize,
+        round: usize,
+        sub_game: usize,
+    },
+    // The sub-game's winner was known without playing it.
+    SubGameSkipped {
+        game: usize,
+        depth: usize,
+        round: usize,
+        winner: usize,
+        reason: SkipReason,
+    },
+    RoundWon {
+        game: usize,
+        depth: usize,
+        round: usize,
+        winner: usize,
+        by_sub_game: bool, // a sub-game was played to decide the round
+    },
+    // The decks repeat an earlier round of the same game, which ends it in favor of the
+    // lowest-numbered player still in it.
+    LoopDetected {
+        game: usize,
+        depth: usize,
+        round: usize,
+        winner: usize,
+    },
+    GameWon {
+        game: usize,
+        depth: usize,
+        winner: usize,
+        decks: Vec<Vec<i64>>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    HighestCard, // the sub-game's lowest-numbered player holds its highest card, so can't lose
+    SeenBefore,  // a sub-game with the same starting decks was already played
+}
+
+impl SkipReason {
+    fn name(&self) -> &'static str {
+        match self {
+            SkipReason::HighestCard => "highest_card",
+            SkipReason::SeenBefore => "seen_before",
+        }
+    }
+}
+
+pub trait TraceSink {
+    // Lets the game skip building events nobody will look at.
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn record(&mut self, event: TraceEvent);
+}
+
+pub struct NoTrace;
+
+impl TraceSink for NoTrace {
+    fn is_enabled(&self) -> bool {
+        false
+    }
+
+    fn record(&mut self, _event: TraceEvent) {}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    Text, // the same wording as the puzzle's worked example
+    Json,
+}
+
+impl TraceFormat {
+    pub fn parse(name: &str) -> TraceFormat {
+        match name {
+            "text" => TraceFormat::Text,
+            "json" => TraceFormat::Json,
+            _ => panic!("unknown trace format: {}", name),
+        }
+    }
+}
+
+fn join_cards(cards: &[i64]) -> String {
+    cards
+        .iter()
+        .map(|card| card.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_text(event: &TraceEvent) -> String {
+    match event {
+        TraceEvent::GameStarted { game, .. } => format!("=== Game {} ===\n", game),
+        TraceEvent::RoundStarted {
+            game,
+            round,
+            decks,
+            cards,
+            ..
+        } => {
+            let mut lines = vec![format!("\n-- Round {} (Game {}) --", round, game)];
+            for (player, deck) in decks.iter().enumerate() {
+                lines.push(format!("Player {}'s deck: {}", player + 1, join_cards(deck)));
+            }
+            for (player, card) in cards.iter().enumerate() {
+                if let Some(card) = card {
+                    lines.push(format!("Player {} plays: {}", player + 1, card));
+                }
+            }
+            lines.join("\n") + "\n"
+        }
+        TraceEvent::SubGameSpawned { .. } => {
+            String::from("Playing a sub-game to determine the winner...\n\n")
+        }
+        TraceEvent::SubGameSkipped { winner, reason, .. } => match reason {
+            SkipReason::HighestCard => format!(
+                "Player {} holds the highest card, so wins the sub-game without playing it.\n",
+                winner
+            ),
+            SkipReason::SeenBefore => format!(
+                "A sub-game with these decks was played before, so player {} wins it again.\n",
+                winner
+            ),
+        },
+        TraceEvent::RoundWon {
+            game,
+            round,
+            winner,
+            by_sub_game,
+            ..
+        } => {
+            let prefix = if *by_sub_game {
+                format!("\n...anyway, back to game {}.\n", game)
+            } else {
+                String::new()
+            };
+            format!(
+                "{}Player {} wins round {} of game {}!\n",
+                prefix, winner, round, game
+            )
+        }
+        TraceEvent::LoopDetected {
+            game,
+            round,
+            winner,
+            ..
+        } => format!(
+            "\n-- Round {} (Game {}) --\nThe decks repeat an earlier round, so player {} wins game {}!\n",
+            round, game, winner, game
+        ),
+        TraceEvent::GameWon {
+            game,
+            depth,
+            winner,
+            decks,
+        } => {
+            let mut text = format!("The winner of game {} is player {}!\n", game, winner);
+            if *depth == 1 {
+                text.push_str("\n\n== Post-game results ==\n");
+                for (player, deck) in decks.iter().enumerate() {
+                    text.push_str(&format!(
+                        "Player {}'s deck: {}\n",
+                        player + 1,
+                        join_cards(deck)
+                    ));
+                }
+            }
+            text
+        }
+    }
+}
+
+fn json_cards(cards: &[i64]) -> String {
+    let cards: Vec<String> = cards.iter().map(|card| card.to_string()).collect();
+    format!("[{}]", cards.join(", "))
+}
+
+fn json_played_cards(cards: &[Option<i64>]) -> String {
+    let cards: Vec<String> = cards
+        .iter()
+        .map(|card| match card {
+            Some(card) => card.to_string(),
+            None => String::from("null"),
+        })
+        .collect();
+    format!("[{}]", cards.join(", "))
+}
+
+fn json_decks(decks: &[Vec<i64>]) -> String {
+    let decks: Vec<String> = decks.iter().map(|deck| json_cards(deck)).collect();
+    format!("[{}]", decks.join(", "))
+}
+
+fn format_json(event: &TraceEvent) -> String {
+    match event {
+        TraceEvent::GameStarted { game, depth } => format!(
+            "{{\"event\": \"game_started\", \"game\": {}, \"depth\": {}}}",
+            game, depth
+        ),
+        TraceEvent::RoundStarted {
+            game,
+            depth,
+            round,
+            decks,
+            cards,
+        } => format!(
+            "{{\"event\": \"round_started\", \"game\": {}, \"depth\": {}, \"round\": {}, \"decks\": {}, \"cards\": {}}}",
+            game,
+            depth,
+            round,
+            json_decks(decks),
+            json_played_cards(cards)
+        ),
+        TraceEvent::SubGameSpawned {
+            game,
+            depth,
+            round,
+            sub_game,
+        } => format!(
+            "{{\"event\": \"sub_game_spawned\", \"game\": {}, \"depth\": {}, \"round\": {}, \"sub_game\": {}}}",
+            game, depth, round, sub_game
+        ),
+        TraceEvent::SubGameSkipped {
+            game,
+            depth,
+            round,
+            winner,
+            reason,
+        } => format!(
+            "{{\"event\": \"sub_game_skipped\", \"game\": {}, \"depth\": {}, \"round\": {}, \"winner\": {}, \"reason\": \"{}\"}}",
+            game,
+            depth,
+            round,
+            winner,
+            reason.name()
+        ),
+        TraceEvent::RoundWon {
+            game,
+            depth,
+            round,
+            winner,
+            by_sub_game,
+        } => format!(
+            "{{\"event\": \"round_won\", \"game\": {}, \"depth\": {}, \"round\": {}, \"winner\": {}, \"by_sub_game\": {}}}",
+            game, depth, round, winner, by_sub_game
+        ),
+        TraceEvent::LoopDetected {
+            game,
+            depth,
+            round,
+            winner,
+        } => format!(
+            "{{\"event\": \"loop_detected\", \"game\": {}, \"depth\": {}, \"round\": {}, \"winner\": {}}}",
+            game, depth, round, winner
+        ),
+        TraceEvent::GameWon {
+            game,
+            depth,
+            winner,
+            decks,
+        } => format!(
+            "{{\"event\": \"game_won\", \"game\": {}, \"depth\": {}, \"winner\": {}, \"decks\": {}}}",
+            game,
+            depth,
+            winner,
+            json_decks(decks)
+        ),
+    }
+}
+
+// Writes events as they happen, since a full trace of a real input can be very large.
+// The JSON format is an array with one event object per line.
+pub struct TraceWriter<W: Write> {
+    output: W,
+    format: TraceFormat,
+    events_written: usize,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> TraceWriter<W> {
+    pub fn new(output: W, format: TraceFormat) -> TraceWriter<W> {
+        TraceWriter {
+            output,
+            format,
+            events_written: 0,
+            error: None,
+        }
+    }
+
+    fn write_event(&mut self, event: &TraceEvent) -> io::Result<()> {
+        match self.format {
+            TraceFormat::Text => self.output.write_all(format_text(event).as_bytes()),
+            TraceFormat::Json => {
+                let separator = if self.events_written == 0 {
+                    "[\n"
+                } else {
+                    ",\n"
+                };
+                write!(self.output, "{}{}", separator, format_json(event))
+            }
+        }
+    }
+
+    // Returns the first error hit while writing, if any, since recording can't report them.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        if self.format == TraceFormat::Json {
+            let closing = if self.events_written == 0 {
+                "[]\n"
+            } else {
+                "\n]\n"
+            };
+            self.output.write_all(closing.as_bytes())?;
+        }
+        self.output.flush()?;
+        Ok(self.output)
+    }
+}
+
+impl<W: Write> TraceSink for TraceWriter<W> {
+    fn is_enabled(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn record(&mut self, event: TraceEvent) {
+        match self.write_event(&event) {
+            Ok(()) => self.events_written += 1,
+            Err(error) => self.error = Some(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::{TraceEvent, TraceFormat, TraceSink, TraceWriter};
+    use crate::{combat::Combat, parse_decks, solve_part2};
+
+    fn make_decks(decks: &[&[i64]]) -> Vec<VecDeque<i64>> {
+        decks
+            .iter()
+            .map(|deck| deck.iter().copied().collect())
+            .collect()
+    }
+
+    struct RecordingTrace {
+        events: Vec<TraceEvent>,
+    }
+
+    impl TraceSink for RecordingTrace {
+        fn record(&mut self, event: TraceEvent) {
+            self.events.push(event);
+        }
+    }
+
+    #[test]
+    fn test_text_trace_matches_puzzle_example() {
+        let decks = parse_decks(include_str!("../sample_input.txt"));
+        let mut trace = TraceWriter::new(vec![], TraceFormat::Text);
+        assert_eq!(291, solve_part2(&decks, &mut trace, false, false));
+        let text = String::from_utf8(trace.finish().unwrap()).unwrap();
+
+        assert!(text.starts_with(
+            "=== Game 1 ===\n\n-- Round 1 (Game 1) --\nPlayer 1's deck: 9, 2, 6, 3, 1\nPlayer 2's deck: 5, 8, 4, 7, 10\nPlayer 1 plays: 9\nPlayer 2 plays: 5\nPlayer 1 wins round 1 of game 1!\n"
+        ));
+        assert!(text.contains(
+            "Player 2 plays: 3\nPlaying a sub-game to determine the winner...\n\n=== Game 2 ===\n"
+        ));
+        assert!(text.contains(
+            "The winner of game 2 is player 2!\n\n...anyway, back to game 1.\nPlayer 2 wins round 9 of game 1!\n"
+        ));
+        assert!(text.ends_with(
+            "== Post-game results ==\nPlayer 1's deck: \nPlayer 2's deck: 7, 5, 6, 2, 4, 1, 10, 8, 9, 3\n"
+        ));
+    }
+
+    #[test]
+    fn test_loop_detection_is_traced() {
+        let decks = parse_decks(include_str!("../sample_input2.txt"));
+        let mut trace = RecordingTrace { events: vec![] };
+        solve_part2(&decks, &mut trace, false, false);
+
+        let loops: Vec<_> = trace
+            .events
+            .iter()
+            .filter(|event| matches!(event, TraceEvent::LoopDetected { .. }))
+            .collect();
+        assert_eq!(
+            vec![&TraceEvent::LoopDetected {
+                game: 1,
+                depth: 1,
+                round: 7,
+                winner: 1,
+            }],
+            loops
+        );
+        assert!(matches!(
+            trace.events.last(),
+            Some(TraceEvent::GameWon { winner: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_loop_after_player_1_is_out() {
+        // player 3 knocks player 1 out in the first round, then players 2 and 3 loop
+        let decks = make_decks(&[&[2], &[1, 3, 4], &[5]]);
+        let mut trace = TraceWriter::new(vec![], TraceFormat::Text);
+        let (winner, _) = Combat::new(&mut trace, false, false).play(&decks);
+        let text = String::from_utf8(trace.finish().unwrap()).unwrap();
+
+        assert_eq!(2, winner);
+        assert!(text.contains("The decks repeat an earlier round, so player 2 wins game 1!\n"));
+        assert!(
+            text.ends_with("Player 1's deck: \nPlayer 2's deck: 4, 2\nPlayer 3's deck: 1, 5, 3\n")
+        );
+    }
+
+    #[test]
+    fn test_json_trace() {
+        let decks = parse_decks(include_str!("../sample_input2.txt"));
+        let mut trace = TraceWriter::new(vec![], TraceFormat::Json);
+        solve_part2(&decks, &mut trace, false, false);
+        let json = String::from_utf8(trace.finish().unwrap()).unwrap();
+
+        assert!(json.starts_with("[\n{\"event\": \"game_started\", \"game\": 1, \"depth\": 1},\n{\"event\": \"round_started\", \"game\": 1, \"depth\": 1, \"round\": 1, \"decks\": [[43, 19], [2, 29, 14]], \"cards\": [43, 2]},\n"));
+        assert!(json.contains(
+            "{\"event\": \"loop_detected\", \"game\": 1, \"depth\": 1, \"round\": 7, \"winner\": 1},\n"
+        ));
+        assert!(json.ends_with("\"winner\": 1, \"decks\": [[43, 19], [2, 29, 14]]}\n]\n"));
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    time::Instant,
+};
+
+use crate::cups::CupCircle;
+
+// Checkpoint layout, all integers little-endian:
+//   magic "CUPS", format version (u32), pick-up count (u32), moves played (u64),
+//   current cup (u32), number of cups (u32), then the successor of each cup from 1 up (u32 each)
+const MAGIC: &[u8; 4] = b"CUPS";
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameState {
+    pub circle: CupCircle,
+    pub moves_played: usize,
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+pub fn write_state(writer: &mut impl Write, state: &GameState) -> io::Result<()> {
+    let successors = &state.circle.successors()[1..];
+    writer.write_all(MAGIC)?;
+    writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    writer.write_all(&(state.circle.pick_up() as u32).to_le_bytes())?;
+    writer.write_all(&(state.moves_played as u64).to_le_bytes())?;
+    writer.write_all(&state.circle.current().to_le_bytes())?;
+    writer.write_all(&(successors.len() as u32).to_le_bytes())?;
+    for cup in successors {
+        writer.write_all(&cup.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+pub fn read_state(reader: &mut impl Read) -> io::Result<GameState> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data(String::from("not a cups checkpoint")));
+    }
+    let version = read_u32(reader)?;
+    if version != FORMAT_VERSION {
+        return Err(invalid_data(format!(
+            "unsupported checkpoint version {}",
+            version
+        )));
+    }
+
+    let pick_up = read_u32(reader)? as usize;
+    let moves_played = read_u64(reader)? as usize;
+    let current = read_u32(reader)?;
+    let total_cups = read_u32(reader)? as usize;
+
+    // The count isn't validated yet, so the table grows as successors are actually read
+    // instead of being allocated up front at whatever size the header claims.
+    let mut next = Vec::with_capacity(total_cups.min(1 << 20) + 1);
+    next.push(0);
+    for _ in 0..total_cups {
+        next.push(read_u32(reader)?);
+    }
+    if reader.read(&mut [0])? != 0 {
+        return Err(invalid_data(String::from("trailing data after checkpoint")));
+    }
+
+    let circle = CupCircle::from_parts(next, current, pick_up).map_err(invalid_data)?;
+    Ok(GameState {
+        circle,
+        moves_played,
+    })
+}
+
+// Writes to a temporary file first, so an interrupted write never clobbers the last
+// good checkpoint.
+pub fn save_checkpoint(path: &str, state: &GameState) -> io::Result<()> {
+    let temporary_path = format!("{}.tmp", path);
+    let mut writer = BufWriter::new(File::create(&temporary_path)?);
+    write_state(&mut writer, state)?;
+    writer.into_inner().map_err(|error| error.into_error())?;
+    fs::rename(&temporary_path, path)
+}
+
+pub fn load_checkpoint(path: &str) -> io::Result<GameState> {
+    read_state(&mut BufReader::new(File::open(path)?))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub progress_every: Option<usize>,
+    pub checkpoint_path: Option<String>,
+    pub checkpoint_every: Option<usize>,
+}
+
+fn report_progress(state: &GameState, target_moves: usize, start: Instant, start_moves: usize) {
+    let elapsed = start.elapsed().as_secs_f64();
+    let moves_per_second = (state.moves_played - start_moves) as f64 / elapsed.max(1e-9);
+    let remaining = (target_moves - state.moves_played) as f64 / moves_per_second.max(1e-9);
+    eprintln!(
+        "move {} of {} ({:.1}%), {:.0} moves/s, about {:.1}s left",
+        state.moves_played,
+        target_moves,
+        100.0 * state.moves_played as f64 / target_moves.max(1) as f64,
+        moves_per_second,
+        remaining
+    );
+}
+
+// Plays until target_moves moves have been played in total, reporting progress and
+// saving checkpoints along the way. A final checkpoint is saved at the end. Moves can't
+// be taken back, so it's an error if more than target_moves have already been played.
+pub fn run_to(state: &mut GameState, target_moves: usize, options: &RunOptions) -> io::Result<()> {
+    if target_moves < state.moves_played {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "can't go back to move {}, the game is already at move {}",
+                target_moves, state.moves_played
+            ),
+        ));
+    }
+    let start = Instant::now();
+    let start_moves = state.moves_played;
+    let checkpoint_every = options
+        .checkpoint_path
+        .as_ref()
+        .and(options.checkpoint_every);
+
+    let next_multiple = |moves: usize, every: Option<usize>| match every {
+        Some(every) if every > 0 => (moves / every + 1) * every,
+        _ => usize::MAX,
+    };
+
+    while state.moves_played < target_moves {
+        let next_progress = next_multiple(state.moves_played, options.progress_every);
+        let next_checkpoint = next_multiple(state.moves_played, checkpoint_every);
+        let stop = target_moves.min(next_progress).min(next_checkpoint);
+
+        state.circle.play(stop - state.moves_played);
+        state.moves_played = stop;
+
+        if stop == next_progress {
+            report_progress(state, target_moves, start, start_moves);
+        }
+        if stop == next_checkpoint && stop != target_moves {
+            save_checkpoint(options.checkpoint_path.as_ref().unwrap(), state)?;
+        }
+    }
+
+    if let Some(path) = &options.checkpoint_path {
+        save_checkpoint(path, state)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_state, run_to, write_state, GameState, RunOptions};
+    use crate::cups::CupCircle;
+
+    const SAMPLE_CUPS: [u32; 9] = [3, 8, 9, 1, 2, 5, 4, 6, 7];
+
+    #[test]
+    fn test_round_trip() {
+        let mut circle = CupCircle::new(&SAMPLE_CUPS, 50, 3);
+        circle.play(123);
+        let state = GameState {
+            circle,
+            moves_played: 123,
+        };
+
+        let mut bytes = vec![];
+        write_state(&mut bytes, &state).unwrap();
+        assert_eq!(4 + 4 + 4 + 8 + 4 + 4 + 50 * 4, bytes.len());
+        assert_eq!(state, read_state(&mut bytes.as_slice()).unwrap());
+
+        // a header claiming four billion cups fails at the end of the data, not allocating
+        let mut huge = bytes[..24].to_vec();
+        huge.extend_from_slice(&u32::MAX.to_le_bytes());
+        huge.extend_from_slice(&bytes[28..]);
+        assert!(read_state(&mut huge.as_slice()).is_err());
+
+        // truncated, corrupted, and padded checkpoints are all rejected
+        assert!(read_state(&mut &bytes[..bytes.len() - 1]).is_err());
+        let mut corrupted = bytes.clone();
+        corrupted[28..32].copy_from_slice(&1u32.to_le_bytes());
+        assert!(read_state(&mut corrupted.as_slice()).is_err());
+        let mut padded = bytes;
+        padded.push(0);
+        assert!(read_state(&mut padded.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_resumed_run_matches_uninterrupted_run() {
+        let mut uninterrupted = GameState {
+            circle: CupCircle::new(&SAMPLE_CUPS, 100, 3),
+            moves_played: 0,
+        };
+        run_to(&mut uninterrupted, 1000, &RunOptions::default()).unwrap();
+
+        let mut first_half = GameState {
+            circle: CupCircle::new(&SAMPLE_CUPS, 100, 3),
+            moves_played: 0,
+        };
+        run_to(&mut first_half, 437, &RunOptions::default()).unwrap();
+        let mut bytes = vec![];
+        write_state(&mut bytes, &first_half).unwrap();
+
+        let mut resumed = read_state(&mut bytes.as_slice()).unwrap();
+        assert_eq!(437, resumed.moves_played);
+        run_to(&mut resumed, 1000, &RunOptions::default()).unwrap();
+        assert_eq!(uninterrupted, resumed);
+
+        assert!(run_to(&mut resumed, 999, &RunOptions::default()).is_err());
+        assert_eq!(uninterrupted, resumed);
+    }
+}
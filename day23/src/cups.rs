@@ -0,0 +1,345 @@
+// The circle of cups as a successor table: next[label] is the label of the cup clockwise
+// from it. Labels are 1 through the number of cups, so index 0 is unused.
+#[derive(Debug, Clone)]
+pub struct CupCircle {
+    next: Vec<u32>,
+    current: u32,
+    pick_up: usize,
+    taken: Vec<u32>, // scratch space for the cups picked up in a move
+}
+
+// the scratch space isn't part of the state
+impl PartialEq for CupCircle {
+    fn eq(&self, other: &CupCircle) -> bool {
+        (&self.next, self.current, self.pick_up) == (&other.next, other.current, other.pick_up)
+    }
+}
+
+impl Eq for CupCircle {}
+
+impl CupCircle {
+    // The given cups come first, in order, followed by the rest of the labels up to
+    // total_cups in increasing order. The first cup is the current one.
+    pub fn new(initial_cups: &[u32], total_cups: usize, pick_up: usize) -> CupCircle {
+        assert!(total_cups >= initial_cups.len());
+        assert!(pick_up >= 1, "a move must pick up at least one cup");
+        assert!(
+            total_cups >= pick_up + 2,
+            "picking up {} cups needs at least {} cups",
+            pick_up,
+            pick_up + 2
+        );
+        assert!(total_cups < u32::MAX as usize);
+
+        let mut seen = vec![false; initial_cups.len() + 1];
+        for &cup in initial_cups {
+            let cup = cup as usize;
+            assert!(
+                (1..=initial_cups.len()).contains(&cup) && !seen[cup],
+                "cups must be labeled 1 through {} without repeats",
+                initial_cups.len()
+            );
+            seen[cup] = true;
+        }
+
+        let order = initial_cups
+            .iter()
+            .copied()
+            .chain((initial_cups.len() as u32 + 1)..=(total_cups as u32));
+        let mut next = vec![0; total_cups + 1];
+        let mut previous: Option<u32> = None;
+        let mut first: Option<u32> = None;
+        for cup in order {
+            match previous {
+                Some(previous) => next[previous as usize] = cup,
+                None => first = Some(cup),
+            }
+            previous = Some(cup);
+        }
+        let first = first.unwrap();
+        next[previous.unwrap() as usize] = first;
+
+        CupCircle {
+            next,
+            current: first,
+            pick_up,
+            taken: Vec::with_capacity(pick_up),
+        }
+    }
+
+    // Rebuilds a circle from its successor table, as saved in a checkpoint.
+    pub fn from_parts(next: Vec<u32>, current: u32, pick_up: usize) -> Result<CupCircle, String> {
+        let total_cups = next.len().saturating_sub(1);
+        if pick_up < 1 || total_cups < pick_up + 2 {
+            return Err(format!(
+                "{} cups are too few to pick up {}",
+                total_cups, pick_up
+            ));
+        }
+        if current < 1 || current as usize > total_cups {
+            return Err(format!("current cup {} is not on the table", current));
+        }
+
+        // following the successors from cup 1 must visit every cup once before coming back
+        let mut visited = vec![false; next.len()];
+        let mut cup = 1;
+        for _ in 0..total_cups {
+            if visited[cup as usize] {
+                return Err(format!("cup {} is reached twice", cup));
+            }
+            visited[cup as usize] = true;
+            cup = next[cup as usize];
+            if cup < 1 || cup as usize > total_cups {
+                return Err(format!("successor {} is not on the table", cup));
+            }
+        }
+        if cup != 1 {
+            return Err(String::from("the successors don't form a single circle"));
+        }
+
+        Ok(CupCircle {
+            next,
+            current,
+            pick_up,
+            taken: Vec::with_capacity(pick_up),
+        })
+    }
+
+    pub fn successors(&self) -> &[u32] {
+        &self.next
+    }
+
+    pub fn current(&self) -> u32 {
+        self.current
+    }
+
+    pub fn pick_up(&self) -> usize {
+        self.pick_up
+    }
+
+    pub fn len(&self) -> usize {
+        self.next.len() - 1
+    }
+
+    pub fn play_move(&mut self) {
+        let max_cup = self.len() as u32;
+        self.taken.clear();
+        let mut last_taken = self.current;
+        for _ in 0..self.pick_up {
+            last_taken = self.next[last_taken as usize];
+            self.taken.push(last_taken);
+        }
+        let next_cup = self.next[last_taken as usize];
+        self.next[self.current as usize] = next_cup;
+
+        let mut destination_cup = self.current;
+        loop {
+            destination_cup = if destination_cup == 1 {
+                max_cup
+            } else {
+                destination_cup - 1
+            };
+            if !self.taken.contains(&destination_cup) {
+                break;
+            }
+        }
+
+        self.next[last_taken as usize] = self.next[destination_cup as usize];
+        self.next[destination_cup as usize] = self.taken[0];
+        self.current = next_cup;
+    }
+
+    pub fn play(&mut self, moves: usize) {
+        for _ in 0..moves {
+            self.play_move();
+        }
+    }
+
+    // The labels of the count cups clockwise from the given one, not including it.
+    pub fn cups_after(&self, cup: u32, count: usize) -> Vec<u32> {
+        let mut result = Vec::with_capacity(count);
+        let mut cup = cup;
+        for _ in 0..count {
+            cup = self.next[cup as usize];
+            result.push(cup);
+        }
+        result
+    }
+
+    // Every cup clockwise from the current one, which is in parentheses like in the puzzle.
+    pub fn describe_board(&self) -> String {
+        let mut labels = vec![format!("({})", self.current)];
+        labels.extend(
+            self.cups_after(self.current, self.len() - 1)
+                .iter()
+                .map(|cup| cup.to_string()),
+        );
+        labels.join(" ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    LabelsAfterOne,         // every label clockwise from cup 1
+    ProductAfterOne(usize), // the product of the labels of this many cups after cup 1
+}
+
+impl OutputMode {
+    // "labels", or "product" optionally followed by ":" and the number of cups
+    pub fn parse(mode: &str) -> OutputMode {
+        match mode.split_once(':') {
+            None if mode == "labels" => OutputMode::LabelsAfterOne,
+            None if mode == "product" => OutputMode::ProductAfterOne(2),
+            Some(("product", count)) => OutputMode::ProductAfterOne(count.parse().unwrap()),
+            _ => panic!("unrecognized output mode: {}", mode),
+        }
+    }
+
+    // Fails if the product doesn't fit in 128 bits, or there aren't enough cups for it.
+    pub fn describe(&self, circle: &CupCircle) -> Result<String, String> {
+        match *self {
+            OutputMode::LabelsAfterOne => {
+                // the puzzle runs single-digit labels together, longer ones need a separator
+                let separator = if circle.len() <= 9 { "" } else { "," };
+                let labels: Vec<String> = circle
+                    .cups_after(1, circle.len() - 1)
+                    .iter()
+                    .map(|cup| cup.to_string())
+                    .collect();
+                Ok(labels.join(separator))
+            }
+            OutputMode::ProductAfterOne(count) => {
+                if count >= circle.len() {
+                    return Err(format!(
+                        "there are only {} cups after cup 1, not {}",
+                        circle.len() - 1,
+                        count
+                    ));
+                }
+                let cups = circle.cups_after(1, count);
+                cups.iter()
+                    .try_fold(1u128, |product, &cup| product.checked_mul(cup as u128))
+                    .map(|product| product.to_string())
+                    .ok_or_else(|| format!("the product of {:?} overflows 128 bits", cups))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GameRules {
+    pub total_cups: Option<usize>, // defaults to just the given cups
+    pub pick_up: usize,
+    pub moves: usize,
+    pub output: OutputMode,
+}
+
+pub fn play_game(initial_cups: &[u32], rules: &GameRules) -> Result<String, String> {
+    let total_cups = rules.total_cups.unwrap_or(initial_cups.len());
+    let mut circle = CupCircle::new(initial_cups, total_cups, rules.pick_up);
+    circle.play(rules.moves);
+    rules.output.describe(&circle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{play_game, CupCircle, GameRules, OutputMode};
+
+    const SAMPLE_CUPS: [u32; 9] = [3, 8, 9, 1, 2, 5, 4, 6, 7];
+
+    #[test]
+    fn test_sample_moves() {
+        let mut circle = CupCircle::new(&SAMPLE_CUPS, 9, 3);
+        circle.play(10);
+        assert_eq!(vec![9, 2, 6, 5, 8, 3, 7, 4], circle.cups_after(1, 8));
+
+        circle.play(90);
+        assert_eq!(vec![6, 7, 3, 8, 4, 5, 2, 9], circle.cups_after(1, 8));
+    }
+
+    #[test]
+    fn test_matches_naive_game() {
+        let initial_cups: Vec<i64> = SAMPLE_CUPS.iter().map(|&cup| cup as i64).collect();
+        for &total_cups in [9, 20, 1000].iter() {
+            let mut circle = CupCircle::new(&SAMPLE_CUPS, total_cups, 3);
+            circle.play(500);
+
+            let all_cups: Vec<i64> = initial_cups
+                .iter()
+                .copied()
+                .chain(10..=total_cups as i64)
+                .collect();
+            let (naive_cups, current_index) = crate::play_game_naively(&all_cups, 500);
+
+            let expected: Vec<u32> = (1..total_cups)
+                .map(|offset| naive_cups[offset] as u32)
+                .collect();
+            assert_eq!(expected, circle.cups_after(1, total_cups - 1));
+            assert_eq!(naive_cups[current_index] as u32, circle.current);
+        }
+    }
+
+    #[test]
+    fn test_from_parts() {
+        let mut circle = CupCircle::new(&SAMPLE_CUPS, 9, 3);
+        circle.play(10);
+        assert_eq!("(8) 3 7 4 1 9 2 6 5", circle.describe_board());
+
+        let rebuilt =
+            CupCircle::from_parts(circle.successors().to_vec(), circle.current(), 3).unwrap();
+        assert_eq!(circle, rebuilt);
+
+        // two separate circles, 1 -> 2 -> 1 and 3 -> 4 -> 5 -> 3
+        assert!(CupCircle::from_parts(vec![0, 2, 1, 4, 5, 3], 1, 1).is_err());
+        assert!(CupCircle::from_parts(vec![0, 2, 3, 4, 5, 1], 6, 1).is_err());
+        assert!(CupCircle::from_parts(vec![0, 2, 3, 4, 5, 1], 1, 1).is_ok());
+    }
+
+    #[test]
+    fn test_pick_up_count() {
+        // picking up one cup from 3 8 9 1 2 5 4 6 7 puts 8 after 2
+        let mut circle = CupCircle::new(&SAMPLE_CUPS, 9, 1);
+        circle.play_move();
+        assert_eq!(vec![2, 8, 5, 4, 6, 7, 3, 9], circle.cups_after(1, 8));
+        assert_eq!(9, circle.current);
+
+        // picking up five cups leaves only two to choose a destination from
+        let mut circle = CupCircle::new(&SAMPLE_CUPS, 9, 5);
+        circle.play_move();
+        assert_eq!(vec![3, 4, 6, 7, 8, 9, 1, 2], circle.cups_after(5, 8));
+    }
+
+    #[test]
+    fn test_output_modes() {
+        assert_eq!(OutputMode::LabelsAfterOne, OutputMode::parse("labels"));
+        assert_eq!(OutputMode::ProductAfterOne(2), OutputMode::parse("product"));
+        assert_eq!(
+            OutputMode::ProductAfterOne(4),
+            OutputMode::parse("product:4")
+        );
+
+        let mut rules = GameRules {
+            total_cups: Some(12),
+            pick_up: 3,
+            moves: 0,
+            output: OutputMode::LabelsAfterOne,
+        };
+        assert_eq!(
+            Ok(String::from("2,5,4,6,7,10,11,12,3,8,9")),
+            play_game(&SAMPLE_CUPS, &rules)
+        );
+
+        rules.output = OutputMode::ProductAfterOne(3);
+        assert_eq!(Ok(String::from("40")), play_game(&SAMPLE_CUPS, &rules));
+
+        rules.output = OutputMode::ProductAfterOne(12);
+        assert!(play_game(&SAMPLE_CUPS, &rules).is_err());
+
+        // 2 * 5 * 4 * 6 * 7 * 10 * 11 * ... * 44 needs more than 128 bits
+        rules.total_cups = Some(50);
+        rules.output = OutputMode::ProductAfterOne(40);
+        assert!(play_game(&SAMPLE_CUPS, &rules).is_err());
+        rules.output = OutputMode::ProductAfterOne(20);
+        assert!(play_game(&SAMPLE_CUPS, &rules).is_ok());
+    }
+}
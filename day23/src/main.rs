@@ -1,45 +1,175 @@
-use std::{
-    collections::{HashMap, VecDeque},
-    fs,
-};
+use std::{collections::HashMap, env, fs, time::Instant};
 
-fn main() {
-    let contents = fs::read_to_string(
-        "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day23/input.txt",
-    )
-    .unwrap();
+use checkpoint::{load_checkpoint, run_to, GameState, RunOptions};
+use cups::{play_game, CupCircle, GameRules, OutputMode};
+
+mod checkpoint;
+mod cups;
+
+#[derive(Debug)]
+struct Options {
+    input_path: String,
+    benchmark_moves: Option<usize>,
+    pick_up: usize,
+    part2_rules: GameRules, // the pick-up count is shared with part 1
+    run_options: RunOptions,
+    resume_path: Option<String>,
+    board_at: Option<usize>,
+}
 
+fn parse_options(args: impl Iterator<Item = String>) -> Options {
+    let mut options = Options {
+        input_path: String::from(
+            "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day23/input.txt",
+        ),
+        benchmark_moves: None,
+        pick_up: 3,
+        part2_rules: GameRules {
+            total_cups: Some(1000000),
+            pick_up: 3,
+            moves: 10000000,
+            output: OutputMode::ProductAfterOne(2),
+        },
+        run_options: RunOptions::default(),
+        resume_path: None,
+        board_at: None,
+    };
+
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--benchmark" => {
+                options.benchmark_moves = Some(
+                    args.next()
+                        .expect("missing number of moves")
+                        .parse()
+                        .unwrap(),
+                )
+            }
+            "--pick-up" => {
+                options.pick_up = args.next().expect("missing pick-up count").parse().unwrap()
+            }
+            "--cups" => {
+                options.part2_rules.total_cups =
+                    Some(args.next().expect("missing cup count").parse().unwrap())
+            }
+            "--moves" => {
+                options.part2_rules.moves = args
+                    .next()
+                    .expect("missing number of moves")
+                    .parse()
+                    .unwrap()
+            }
+            "--output" => {
+                options.part2_rules.output =
+                    OutputMode::parse(&args.next().expect("missing output mode"))
+            }
+            "--progress" => {
+                options.run_options.progress_every =
+                    Some(args.next().expect("missing move interval").parse().unwrap())
+            }
+            "--checkpoint" => {
+                options.run_options.checkpoint_path =
+                    Some(args.next().expect("missing checkpoint path"))
+            }
+            "--checkpoint-every" => {
+                options.run_options.checkpoint_every =
+                    Some(args.next().expect("missing move interval").parse().unwrap())
+            }
+            "--resume" => options.resume_path = Some(args.next().expect("missing checkpoint path")),
+            "--board-at" => {
+                options.board_at = Some(args.next().expect("missing move number").parse().unwrap())
+            }
+            _ if arg.starts_with("--") => panic!("unrecognized option: {}", arg),
+            _ => options.input_path = arg,
+        }
+    }
+
+    options.part2_rules.pick_up = options.pick_up;
+    options
+}
+
+// Either a run of single-digit labels like the puzzle's, or comma-separated labels.
+fn parse_cups(contents: &str) -> Vec<u32> {
     let lines: Vec<_> = contents.trim().split('\n').collect();
     assert_eq!(lines.len(), 1);
-    let cups: VecDeque<i64> = lines[0]
-        .chars()
-        .map(|x| x.to_string().parse().unwrap())
-        .collect();
+    if lines[0].contains(',') {
+        lines[0]
+            .split(',')
+            .map(|x| x.trim().parse().unwrap())
+            .collect()
+    } else {
+        lines[0].chars().map(|x| x.to_digit(10).unwrap()).collect()
+    }
+}
+
+fn main() {
+    let options = parse_options(env::args().skip(1));
 
-    println!("{}", solve_part1(&cups, 100));
-    println!("{}", solve_part2(&cups, 10000000));
+    // A resumed game already has its cups, so there's no input to read and no part 1.
+    let mut state = match &options.resume_path {
+        Some(resume_path) => match load_checkpoint(resume_path) {
+            Ok(state) => state,
+            Err(error) => {
+                println!("can't resume from {}: {}", resume_path, error);
+                return;
+            }
+        },
+        None => {
+            let contents = fs::read_to_string(&options.input_path).unwrap();
+            let cups = parse_cups(&contents);
+
+            if let Some(moves) = options.benchmark_moves {
+                run_benchmark(&cups, options.part2_rules.total_cups.unwrap(), moves);
+                return;
+            }
+
+            if options.board_at.is_none() {
+                println!("{}", solve_part1(&cups, options.pick_up, 100));
+            }
+            new_part2_game(&cups, &options.part2_rules)
+        }
+    };
+
+    if let Some(move_number) = options.board_at {
+        match run_to(&mut state, move_number, &options.run_options) {
+            Ok(()) => println!(
+                "cups after move {}: {}",
+                move_number,
+                state.circle.describe_board()
+            ),
+            Err(error) => println!("{}", error),
+        }
+        return;
+    }
+
+    match solve_part2(&mut state, &options.part2_rules, &options.run_options) {
+        Ok(answer) => println!("{}", answer),
+        Err(message) => println!("{}", message),
+    }
 }
 
 fn reconstruct_board(
     clockwise_neighbor: &HashMap<i64, i64>,
     current_cup: i64,
     num_cups: usize,
-) -> (VecDeque<i64>, usize) {
+) -> (Vec<i64>, usize) {
     let mut current_cup_index: usize = 0;
-    let mut final_cups = VecDeque::new();
+    let mut final_cups = Vec::new();
     let mut starting_cup: i64 = 1;
     for index in 0..num_cups {
         if starting_cup == current_cup {
             current_cup_index = index;
         }
-        final_cups.push_back(starting_cup);
+        final_cups.push(starting_cup);
         starting_cup = clockwise_neighbor[&starting_cup];
     }
 
     (final_cups, current_cup_index)
 }
 
-fn play_game_naively(initial_cups: &VecDeque<i64>, moves: usize) -> (VecDeque<i64>, usize) {
+// The original HashMap-based game, kept as a reference for tests and the benchmark.
+fn play_game_naively(initial_cups: &[i64], moves: usize) -> (Vec<i64>, usize) {
     let mut clockwise_neighbor: HashMap<i64, i64> = initial_cups
         .iter()
         .copied()
@@ -86,45 +216,91 @@ fn play_game_naively(initial_cups: &VecDeque<i64>, moves: usize) -> (VecDeque<i6
     reconstruct_board(&clockwise_neighbor, current_cup, initial_cups.len())
 }
 
-fn solve_part1(initial_cups: &VecDeque<i64>, moves: usize) -> String {
-    let (cups, _) = play_game_naively(initial_cups, moves);
-
-    let one_cup_index = cups
-        .iter()
-        .enumerate()
-        .filter_map(|(index, &cup)| if cup == 1 { Some(index) } else { None })
-        .next()
-        .unwrap();
+fn solve_part1(initial_cups: &[u32], pick_up: usize, moves: usize) -> String {
+    let rules = GameRules {
+        total_cups: None,
+        pick_up,
+        moves,
+        output: OutputMode::LabelsAfterOne,
+    };
+    play_game(initial_cups, &rules).expect("listing the labels can't fail")
+}
 
-    let cups_in_order: Vec<String> = (1..cups.len())
-        .map(|offset| cups[(one_cup_index + offset) % cups.len()].to_string())
-        .collect();
+fn new_part2_game(initial_cups: &[u32], rules: &GameRules) -> GameState {
+    let total_cups = rules.total_cups.unwrap_or(initial_cups.len());
+    GameState {
+        circle: CupCircle::new(initial_cups, total_cups, rules.pick_up),
+        moves_played: 0,
+    }
+}
 
-    cups_in_order.join("")
+// Plays the rest of the game from wherever the given state is.
+fn solve_part2(
+    state: &mut GameState,
+    rules: &GameRules,
+    run_options: &RunOptions,
+) -> Result<String, String> {
+    run_to(state, rules.moves, run_options).map_err(|error| error.to_string())?;
+    rules.output.describe(&state.circle)
 }
 
-fn solve_part2(initial_cups: &VecDeque<i64>, moves: usize) -> i64 {
-    let max_cup = initial_cups.iter().copied().max().unwrap();
-    let all_initial_cups: VecDeque<_> = initial_cups
+// Plays the part 2 setup for the given number of moves with both the HashMap and the
+// successor table, and checks that they end with the same board.
+fn run_benchmark(initial_cups: &[u32], total_cups: usize, moves: usize) {
+    let start = Instant::now();
+    let mut circle = CupCircle::new(initial_cups, total_cups, 3);
+    circle.play(moves);
+    let table_board: Vec<i64> = std::iter::once(1)
+        .chain(circle.cups_after(1, total_cups - 1))
+        .map(|cup| cup as i64)
+        .collect();
+    let table_time = start.elapsed();
+
+    let start = Instant::now();
+    let all_initial_cups: Vec<i64> = initial_cups
         .iter()
-        .copied()
-        .chain(max_cup + 1..=1000000)
+        .map(|&cup| cup as i64)
+        .chain(initial_cups.len() as i64 + 1..=total_cups as i64)
         .collect();
+    let (naive_board, _) = play_game_naively(&all_initial_cups, moves);
+    let naive_time = start.elapsed();
 
-    let (final_cups, _) = play_game_naively(&all_initial_cups, moves);
+    assert_eq!(naive_board, table_board);
+    println!("{} moves with {} cups:", moves, total_cups);
+    println!("  HashMap:         {:>10.3?}", naive_time);
+    println!("  successor table: {:>10.3?}", table_time);
+    println!(
+        "  speedup: {:.1}x",
+        naive_time.as_secs_f64() / table_time.as_secs_f64()
+    );
+}
 
-    let one_cup_index = final_cups
-        .iter()
-        .enumerate()
-        .filter_map(|(index, &cup)| if cup == 1 { Some(index) } else { None })
-        .next()
-        .unwrap();
+#[cfg(test)]
+mod tests {
+    use super::{new_part2_game, parse_cups, parse_options, solve_part1, solve_part2};
 
-    let next_two_cups_in_order: Vec<i64> = (1..final_cups.len())
-        .map(|offset| final_cups[(one_cup_index + offset) % final_cups.len()])
-        .take(2)
-        .collect();
+    #[test]
+    fn test_sample_part1() {
+        let cups = parse_cups(include_str!("../sample_input.txt"));
+        assert_eq!("92658374", solve_part1(&cups, 3, 10));
+        assert_eq!("67384529", solve_part1(&cups, 3, 100));
+    }
 
-    println!("two cups: {:?}", next_two_cups_in_order);
-    next_two_cups_in_order[0] * next_two_cups_in_order[1]
+    #[test]
+    fn test_sample_part2() {
+        let cups = parse_cups(include_str!("../sample_input.txt"));
+        let options = parse_options(std::iter::empty());
+        let mut state = new_part2_game(&cups, &options.part2_rules);
+        assert_eq!(
+            "149245887792",
+            solve_part2(&mut state, &options.part2_rules, &options.run_options).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_comma_separated_labels() {
+        let digits = parse_cups(include_str!("../sample_input.txt"));
+        assert_eq!(digits, parse_cups("3,8,9,1,2,5,4,6,7\n"));
+        assert_eq!(vec![10, 2, 1, 11], parse_cups("10, 2, 1, 11"));
+    }
 }
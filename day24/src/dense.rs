@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+
+use hexgrid::Axial;
+
+use crate::rules::HexLifeRules;
+
+// How much room to add on each side whenever black tiles reach the edge of the window.
+const GROWTH_WORDS: usize = 1;
+const GROWTH_ROWS: usize = 64;
+
+// The floor as a window of bits over axial coordinates: one row of words per r, with
+// bit i of a row standing for q = min_q + i. Tiles outside the window are white, and
+// the window grows whenever a black tile gets to its edge, so every tile that can
+// turn black in the next day is inside it.
+#[derive(Debug, Clone)]
+pub struct DenseFloor {
+    min_q: i64,
+    min_r: i64,
+    words_per_row: usize,
+    rows: usize,
+    bits: Vec<u64>,
+}
+
+impl DenseFloor {
+    pub fn new(black_tiles: &HashSet<Axial>) -> DenseFloor {
+        let min_q = black_tiles.iter().map(|tile| tile.q).min().unwrap_or(0);
+        let max_q = black_tiles.iter().map(|tile| tile.q).max().unwrap_or(0);
+        let min_r = black_tiles.iter().map(|tile| tile.r).min().unwrap_or(0);
+        let max_r = black_tiles.iter().map(|tile| tile.r).max().unwrap_or(0);
+
+        let words_per_row = ((max_q - min_q) as usize + 1).div_ceil(64) + 2 * GROWTH_WORDS;
+        let rows = (max_r - min_r) as usize + 1 + 2 * GROWTH_ROWS;
+        let mut floor = DenseFloor {
+            min_q: min_q - 64 * GROWTH_WORDS as i64,
+            min_r: min_r - GROWTH_ROWS as i64,
+            words_per_row,
+            rows,
+            bits: vec![0; words_per_row * rows],
+        };
+        for &tile in black_tiles {
+            floor.set(tile);
+        }
+        floor
+    }
+
+    fn set(&mut self, tile: Axial) {
+        let column = (tile.q - self.min_q) as usize;
+        let row = (tile.r - self.min_r) as usize;
+        self.bits[row * self.words_per_row + column / 64] |= 1 << (column % 64);
+    }
+
+    fn row(&self, row: usize) -> &[u64] {
+        &self.bits[row * self.words_per_row..(row + 1) * self.words_per_row]
+    }
+
+    pub fn count(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn black_tiles(&self) -> HashSet<Axial> {
+        let mut result = HashSet::with_capacity(self.count());
+        for row in 0..self.rows {
+            for (word_index, &word) in self.row(row).iter().enumerate() {
+                let mut word = word;
+                while word != 0 {
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    result.insert(Axial::new(
+                        self.min_q + (word_index * 64 + bit) as i64,
+                        self.min_r + row as i64,
+                    ));
+                }
+            }
+        }
+        result
+    }
+
+    fn touches_edge(&self) -> bool {
+        let last_word = self.words_per_row - 1;
+        self.row(0).iter().any(|&word| word != 0)
+            || self.row(self.rows - 1).iter().any(|&word| word != 0)
+            || (0..self.rows).any(|row| {
+                let words = self.row(row);
+                words[0] & 1 != 0 || words[last_word] >> 63 != 0
+            })
+    }
+
+    fn grow(&mut self) {
+        let words_per_row = self.words_per_row + 2 * GROWTH_WORDS;
+        let rows = self.rows + 2 * GROWTH_ROWS;
+        let mut bits = vec![0; words_per_row * rows];
+        for row in 0..self.rows {
+            let start = (row + GROWTH_ROWS) * words_per_row + GROWTH_WORDS;
+            bits[start..start + self.words_per_row].copy_from_slice(self.row(row));
+        }
+
+        self.min_q -= 64 * GROWTH_WORDS as i64;
+        self.min_r -= GROWTH_ROWS as i64;
+        self.words_per_row = words_per_row;
+        self.rows = rows;
+        self.bits = bits;
+    }
+
+    pub fn step(&mut self, rules: &HexLifeRules) {
+        if self.touches_edge() {
+            self.grow();
+        }
+
+        // The rules as a truth table over the tile's color and its neighbor count,
+        // listing the (is_black, count) combinations that end up black.
+        let black_cases: Vec<(bool, usize)> = [false, true]
+            .iter()
+            .flat_map(|&is_black| (0..=6).map(move |count| (is_black, count)))
+            .filter(|&(is_black, count)| rules.next_color(is_black, count))
+            .collect();
+
+        let empty_row = vec![0; self.words_per_row];
+        let mut next_bits = vec![0; self.bits.len()];
+        for row in 0..self.rows {
+            let above = if row > 0 {
+                self.row(row - 1)
+            } else {
+                &empty_row
+            };
+            let current = self.row(row);
+            let below = if row + 1 < self.rows {
+                self.row(row + 1)
+            } else {
+                &empty_row
+            };
+
+            for word in 0..self.words_per_row {
+                let next_word = |words: &[u64]| words.get(word + 1).copied().unwrap_or(0);
+                let previous_word = |words: &[u64]| {
+                    word.checked_sub(1)
+                        .map(|previous| words[previous])
+                        .unwrap_or(0)
+                };
+                // bit i of these is the tile at q - 1 or q + 1 from bit i of the row
+                let west = |words: &[u64]| (words[word] << 1) | (previous_word(words) >> 63);
+                let east = |words: &[u64]| (words[word] >> 1) | (next_word(words) << 63);
+
+                // neighbors: (q - 1, r), (q + 1, r), (q, r - 1), (q + 1, r - 1),
+                // (q - 1, r + 1) and (q, r + 1)
+                let (ones, twos, fours) = count_six(
+                    west(current),
+                    east(current),
+                    above[word],
+                    east(above),
+                    west(below),
+                    below[word],
+                );
+
+                let black = current[word];
+                let mut result = 0;
+                for &(is_black, count) in black_cases.iter() {
+                    let select = |bit: bool, plane: u64| if bit { plane } else { !plane };
+                    result |= select(is_black, black)
+                        & select(count & 1 != 0, ones)
+                        & select(count & 2 != 0, twos)
+                        & select(count & 4 != 0, fours);
+                }
+                next_bits[row * self.words_per_row + word] = result;
+            }
+        }
+
+        self.bits = next_bits;
+    }
+}
+
+// Adds six one-bit numbers in every bit position at once, returning the bits of the sums.
+fn count_six(a: u64, b: u64, c: u64, d: u64, e: u64, f: u64) -> (u64, u64, u64) {
+    let full_add = |x: u64, y: u64, z: u64| (x ^ y ^ z, (x & y) | (z & (x ^ y)));
+
+    let (first_ones, first_twos) = full_add(a, b, c);
+    let (second_ones, second_twos) = full_add(d, e, f);
+    let ones = first_ones ^ second_ones;
+    let carry = first_ones & second_ones;
+    let (twos, fours) = full_add(first_twos, second_twos, carry);
+    (ones, twos, fours)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use hexgrid::{spiral, Axial};
+
+    use super::{count_six, DenseFloor};
+    use crate::rules::HexLifeRules;
+
+    #[test]
+    fn test_count_six() {
+        for inputs in 0u64..64 {
+            let bit = |index: u64| (inputs >> index) & 1;
+            let (ones, twos, fours) = count_six(bit(0), bit(1), bit(2), bit(3), bit(4), bit(5));
+            assert_eq!(
+                inputs.count_ones() as u64,
+                ones + 2 * twos + 4 * fours,
+                "inputs {:06b}",
+                inputs
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let black_tiles: HashSet<Axial> = vec![
+            Axial::new(0, 0),
+            Axial::new(-70, 3),
+            Axial::new(100, -40),
+            Axial::new(63, 1),
+        ]
+        .into_iter()
+        .collect();
+        let floor = DenseFloor::new(&black_tiles);
+        assert_eq!(4, floor.count());
+        assert_eq!(black_tiles, floor.black_tiles());
+        assert!(DenseFloor::new(&HashSet::new()).black_tiles().is_empty());
+    }
+
+    #[test]
+    fn test_matches_hash_set_backend() {
+        // a filled hexagon grows in every direction and crosses word boundaries quickly
+        let mut expected: HashSet<Axial> = spiral(Axial::new(60, -2), 3).into_iter().collect();
+        let mut floor = DenseFloor::new(&expected);
+        for rules in ["B2/S12", "B23/S2"].iter() {
+            let rules = HexLifeRules::parse(rules).unwrap();
+            for _day in 0..40 {
+                expected = crate::simulate_day(&expected, &rules);
+                floor.step(&rules);
+                assert_eq!(expected, floor.black_tiles());
+            }
+        }
+    }
+}
@@ -1,105 +1,177 @@
-use std::{collections::HashSet, fs};
+use std::{collections::HashSet, env, fs, path::Path, time::Instant};
 
-fn main() {
-    let contents = fs::read_to_string(
-        "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day24/input.txt",
-    )
-    .unwrap();
+use dense::DenseFloor;
+use hexgrid::{Axial, HexDirection};
+use render::{render_ascii, render_svg, Bounds};
+use rules::HexLifeRules;
+use stats::{to_csv, DayStats};
 
-    let all_directions: Vec<_> = contents.trim().split('\n').map(parse_directions).collect();
+mod dense;
+mod render;
+mod rules;
+mod stats;
 
-    println!("{}", solve_part1(&all_directions));
-    println!("{}", solve_part2(&all_directions));
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    HashSet, // only the black tiles, as a set of coordinates
+    Dense,   // a bitset over a window of the floor
+}
+
+impl Backend {
+    fn parse(backend: &str) -> Backend {
+        match backend {
+            "hashset" => Backend::HashSet,
+            "dense" => Backend::Dense,
+            _ => panic!("unrecognized backend: {}", backend),
+        }
+    }
 }
 
 #[derive(Debug)]
-enum HexDirection {
-    East,
-    SouthEast,
-    SouthWest,
-    West,
-    NorthWest,
-    NorthEast,
+struct Options {
+    input_path: String,
+    days: usize,
+    rules: HexLifeRules,
+    backend: Backend,
+    benchmark_days: Option<usize>,
+    svg_dir: Option<String>,
+    ascii_dir: Option<String>,
+    csv_path: Option<String>,
+    stats: bool,
 }
 
-impl HexDirection {
-    fn variants() -> &'static [HexDirection] {
-        &[
-            HexDirection::East,
-            HexDirection::SouthEast,
-            HexDirection::SouthWest,
-            HexDirection::West,
-            HexDirection::NorthWest,
-            HexDirection::NorthEast,
-        ]
-    }
-
-    fn to_coordinates(&self) -> (i64, i64) {
-        match self {
-            HexDirection::East => (1, 0),
-            HexDirection::West => (-1, 0),
-            HexDirection::SouthEast => (0, 1),
-            HexDirection::NorthWest => (0, -1),
-            HexDirection::SouthWest => (-1, 1),
-            HexDirection::NorthEast => (1, -1),
+fn parse_options(args: impl Iterator<Item = String>) -> Options {
+    let mut options = Options {
+        input_path: String::from(
+            "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day24/input.txt",
+        ),
+        days: 100,
+        rules: HexLifeRules::PUZZLE,
+        backend: Backend::HashSet,
+        benchmark_days: None,
+        svg_dir: None,
+        ascii_dir: None,
+        csv_path: None,
+        stats: false,
+    };
+
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--days" => options.days = args.next().expect("missing days").parse().unwrap(),
+            "--rules" => {
+                options.rules = HexLifeRules::parse(&args.next().expect("missing rules")).unwrap()
+            }
+            "--backend" => options.backend = Backend::parse(&args.next().expect("missing backend")),
+            "--benchmark" => {
+                options.benchmark_days = Some(args.next().expect("missing days").parse().unwrap())
+            }
+            "--csv" => options.csv_path = Some(args.next().expect("missing CSV path")),
+            "--stats" => options.stats = true,
+            "--svg" => options.svg_dir = Some(args.next().expect("missing SVG directory")),
+            "--ascii" => options.ascii_dir = Some(args.next().expect("missing ASCII directory")),
+            _ if arg.starts_with("--") => panic!("unrecognized option: {}", arg),
+            _ => options.input_path = arg,
         }
     }
+
+    options
 }
 
-fn parse_directions(line: &str) -> Vec<HexDirection> {
-    let mut result = Vec::new();
-    let mut remaining_line = line;
+fn main() {
+    let options = parse_options(env::args().skip(1));
+    let contents = fs::read_to_string(&options.input_path).unwrap();
 
-    loop {
-        let (next_direction, remainder) = parse_next_direction(remaining_line);
-        remaining_line = remainder;
-        match next_direction {
-            None => break,
-            Some(direction) => result.push(direction),
-        }
+    let all_directions: Vec<_> = contents.trim().split('\n').map(parse_directions).collect();
+
+    if let Some(days) = options.benchmark_days {
+        run_benchmark(&all_directions, &options.rules, days);
+        return;
     }
 
-    result
-}
+    println!("{}", solve_part1(&all_directions));
+    println!(
+        "{}",
+        solve_part2(
+            &all_directions,
+            &options.rules,
+            options.days,
+            options.backend
+        )
+    );
 
-fn parse_next_direction(line: &str) -> (Option<HexDirection>, &str) {
-    let mut char_iter = line.chars();
-
-    match char_iter.next() {
-        None => (None, line),
-        Some('e') => (Some(HexDirection::East), &line[1..]),
-        Some('w') => (Some(HexDirection::West), &line[1..]),
-        Some('s') => match char_iter.next() {
-            Some('e') => (Some(HexDirection::SouthEast), &line[2..]),
-            Some('w') => (Some(HexDirection::SouthWest), &line[2..]),
-            _ => unreachable!(),
-        },
-        Some('n') => match char_iter.next() {
-            Some('e') => (Some(HexDirection::NorthEast), &line[2..]),
-            Some('w') => (Some(HexDirection::NorthWest), &line[2..]),
-            _ => unreachable!(),
-        },
-        _ => unreachable!(),
+    let wants_history = options.svg_dir.is_some()
+        || options.ascii_dir.is_some()
+        || options.csv_path.is_some()
+        || options.stats;
+    if !wants_history {
+        return;
     }
-}
 
-fn get_coordinates_from_directions(directions: &[HexDirection]) -> (i64, i64) {
-    // https://www.redblobgames.com/grids/hexagons/#coordinates-axial
-    let mut axial_q: i64 = 0;
-    let mut axial_r: i64 = 0;
+    let history = simulate_floor(
+        &all_directions,
+        &options.rules,
+        options.days,
+        options.backend,
+    );
+    render_frames(&history, &options);
+
+    let series: Vec<DayStats> = history
+        .iter()
+        .enumerate()
+        .map(|(day, black_tiles)| DayStats::measure(day, black_tiles))
+        .collect();
+    if let Some(csv_path) = &options.csv_path {
+        fs::write(csv_path, to_csv(&series)).unwrap();
+    }
+    if options.stats {
+        println!("{}", series[0].describe());
+        println!("{}", series[series.len() - 1].describe());
+
+        let largest = series
+            .iter()
+            .filter_map(|stats| {
+                stats
+                    .bounds
+                    .map(|bounds| (bounds.columns() * bounds.rows(), stats))
+            })
+            .max_by_key(|&(area, stats)| (area, std::cmp::Reverse(stats.day)));
+        if let Some((area, stats)) = largest {
+            println!("largest bounding box: {} tiles on day {}", area, stats.day);
+        }
+        let busiest = series
+            .iter()
+            .max_by_key(|stats| (stats.black_tiles, std::cmp::Reverse(stats.day)))
+            .unwrap();
+        println!(
+            "most black tiles: {} on day {}",
+            busiest.black_tiles, busiest.day
+        );
+    }
+}
 
-    for direction in directions {
-        let (off_q, off_r) = direction.to_coordinates();
+fn parse_directions(line: &str) -> Vec<HexDirection> {
+    let mut result = Vec::new();
+    let mut remaining_line = line;
 
-        axial_q += off_q;
-        axial_r += off_r;
+    while !remaining_line.is_empty() {
+        let (direction, remainder) = HexDirection::parse_prefix(remaining_line)
+            .unwrap_or_else(|| panic!("invalid directions: {}", remaining_line));
+        result.push(direction);
+        remaining_line = remainder;
     }
 
-    (axial_q, axial_r)
+    result
 }
 
-fn calculate_initial_flipped_tiles(all_directions: &[Vec<HexDirection>]) -> HashSet<(i64, i64)> {
-    let mut flipped_tiles: HashSet<(i64, i64)> = HashSet::new();
+fn get_coordinates_from_directions(directions: &[HexDirection]) -> Axial {
+    directions
+        .iter()
+        .fold(Axial::ORIGIN, |tile, &direction| tile.neighbor(direction))
+}
+
+fn calculate_initial_flipped_tiles(all_directions: &[Vec<HexDirection>]) -> HashSet<Axial> {
+    let mut flipped_tiles: HashSet<Axial> = HashSet::new();
 
     for directions in all_directions {
         let flipped_tile = get_coordinates_from_directions(directions);
@@ -117,51 +189,180 @@ fn solve_part1(all_directions: &[Vec<HexDirection>]) -> usize {
     calculate_initial_flipped_tiles(all_directions).len()
 }
 
-fn solve_part2(all_directions: &[Vec<HexDirection>]) -> usize {
-    let initial_flips = calculate_initial_flipped_tiles(all_directions);
-
-    let mut currently_black_tiles;
-    let mut next_black_tiles = initial_flips;
+fn simulate_day(currently_black_tiles: &HashSet<Axial>, rules: &HexLifeRules) -> HashSet<Axial> {
+    let mut next_black_tiles = currently_black_tiles.clone();
 
-    let days_to_simulate = 100;
-    for _day in 1..=days_to_simulate {
-        currently_black_tiles = next_black_tiles.clone();
+    let possibly_active_tiles: HashSet<Axial> = currently_black_tiles
+        .iter()
+        .flat_map(|&tile| std::iter::once(tile).chain(tile.neighbors()))
+        .collect();
 
-        let possibly_active_tiles: HashSet<(i64, i64)> = currently_black_tiles
-            .iter()
-            .flat_map(|&(q, r)| {
-                let mut result = vec![(q, r)];
-                result.extend(
-                    HexDirection::variants()
-                        .iter()
-                        .map(HexDirection::to_coordinates)
-                        .map(|(dq, dr)| (q + dq, r + dr)),
-                );
-                result
-            })
-            .collect();
+    for tile in possibly_active_tiles {
+        let is_black_tile = currently_black_tiles.contains(&tile);
 
-        for (q, r) in possibly_active_tiles {
-            let is_black_tile = currently_black_tiles.contains(&(q, r));
-
-            let num_black_neighbors = HexDirection::variants()
-                .iter()
-                .map(HexDirection::to_coordinates)
-                .map(|(dq, dr)| (q + dq, r + dr))
-                .filter(|coords| currently_black_tiles.contains(coords))
-                .count();
+        let num_black_neighbors = tile
+            .neighbors()
+            .filter(|neighbor| currently_black_tiles.contains(neighbor))
+            .count();
 
+        if rules.next_color(is_black_tile, num_black_neighbors) != is_black_tile {
             if is_black_tile {
-                if num_black_neighbors == 0 || num_black_neighbors > 2 {
-                    let removed = next_black_tiles.remove(&(q, r));
-                    assert!(removed);
-                }
-            } else if num_black_neighbors == 2 {
-                let added = next_black_tiles.insert((q, r));
+                let removed = next_black_tiles.remove(&tile);
+                assert!(removed);
+            } else {
+                let added = next_black_tiles.insert(tile);
                 assert!(added);
             }
         }
     }
 
-    next_black_tiles.len()
+    next_black_tiles
+}
+
+fn solve_part2(
+    all_directions: &[Vec<HexDirection>],
+    rules: &HexLifeRules,
+    days: usize,
+    backend: Backend,
+) -> usize {
+    let mut black_tiles = calculate_initial_flipped_tiles(all_directions);
+
+    match backend {
+        Backend::HashSet => {
+            for _day in 1..=days {
+                black_tiles = simulate_day(&black_tiles, rules);
+            }
+            black_tiles.len()
+        }
+        Backend::Dense => {
+            let mut floor = DenseFloor::new(&black_tiles);
+            for _day in 1..=days {
+                floor.step(rules);
+            }
+            floor.count()
+        }
+    }
+}
+
+// The black tiles at the start and after each day.
+fn simulate_floor(
+    all_directions: &[Vec<HexDirection>],
+    rules: &HexLifeRules,
+    days: usize,
+    backend: Backend,
+) -> Vec<HashSet<Axial>> {
+    let mut history = vec![calculate_initial_flipped_tiles(all_directions)];
+    match backend {
+        Backend::HashSet => {
+            for _day in 1..=days {
+                history.push(simulate_day(history.last().unwrap(), rules));
+            }
+        }
+        Backend::Dense => {
+            let mut floor = DenseFloor::new(&history[0]);
+            for _day in 1..=days {
+                floor.step(rules);
+                history.push(floor.black_tiles());
+            }
+        }
+    }
+    history
+}
+
+// Times both backends over the given number of days and checks that they agree.
+fn run_benchmark(all_directions: &[Vec<HexDirection>], rules: &HexLifeRules, days: usize) {
+    let mut results = vec![];
+    for &backend in [Backend::HashSet, Backend::Dense].iter() {
+        let start = Instant::now();
+        let black_tiles = solve_part2(all_directions, rules, days, backend);
+        results.push((backend, black_tiles, start.elapsed()));
+    }
+
+    println!("{} days of {}:", days, rules);
+    for (backend, black_tiles, elapsed) in results.iter() {
+        println!(
+            "  {:?}: {} black tiles in {:.3?}",
+            backend, black_tiles, elapsed
+        );
+    }
+    assert_eq!(results[0].1, results[1].1, "the backends disagree");
+    println!(
+        "  speedup: {:.1}x",
+        results[0].2.as_secs_f64() / results[1].2.as_secs_f64()
+    );
+}
+
+// Writes the floor as it is at the start and after each day, one file per day.
+fn render_frames(frames: &[HashSet<Axial>], options: &Options) {
+    let bounds = Bounds::around(frames.iter().flatten())
+        .or_else(|| Bounds::around(&[Axial::ORIGIN]))
+        .unwrap();
+
+    let outputs = [(&options.svg_dir, "svg"), (&options.ascii_dir, "txt")];
+    for (directory, extension) in outputs.iter() {
+        let directory = match directory {
+            Some(directory) => Path::new(directory),
+            None => continue,
+        };
+        fs::create_dir_all(directory).unwrap();
+
+        for (day, black_tiles) in frames.iter().enumerate() {
+            let data = if *extension == "svg" {
+                let caption = format!("Day {}: {} black tiles", day, black_tiles.len());
+                render_svg(black_tiles, &bounds, &caption)
+            } else {
+                render_ascii(black_tiles, &bounds) + "\n"
+            };
+            let path = directory.join(format!("day{:03}.{}", day, extension));
+            fs::write(path, data).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_directions, simulate_floor, solve_part1, solve_part2, Backend};
+    use crate::rules::HexLifeRules;
+
+    #[test]
+    fn test_sample() {
+        let all_directions: Vec<_> = include_str!("../sample_input.txt")
+            .trim()
+            .split('\n')
+            .map(parse_directions)
+            .collect();
+        assert_eq!(10, solve_part1(&all_directions));
+        assert_eq!(
+            2208,
+            solve_part2(
+                &all_directions,
+                &HexLifeRules::PUZZLE,
+                100,
+                Backend::HashSet
+            )
+        );
+        assert_eq!(
+            2208,
+            solve_part2(&all_directions, &HexLifeRules::PUZZLE, 100, Backend::Dense)
+        );
+    }
+
+    #[test]
+    fn test_sample_history() {
+        let all_directions: Vec<_> = include_str!("../sample_input.txt")
+            .trim()
+            .split('\n')
+            .map(parse_directions)
+            .collect();
+
+        // the populations listed in the puzzle for the first few days
+        let history = simulate_floor(&all_directions, &HexLifeRules::PUZZLE, 5, Backend::Dense);
+        let populations: Vec<usize> = history.iter().map(|tiles| tiles.len()).collect();
+        assert_eq!(vec![10, 15, 12, 25, 14, 23], populations);
+
+        // with no way to flip to black, nothing ever outlives a day with no black neighbors
+        let rules = HexLifeRules::parse("B/S123456").unwrap();
+        let history = simulate_floor(&all_directions, &rules, 3, Backend::HashSet);
+        assert!(history.windows(2).all(|pair| pair[1].is_subset(&pair[0])));
+    }
 }
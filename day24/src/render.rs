@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+
+use hexgrid::{Axial, Offset, OffsetLayout};
+
+// Rows alternate between two horizontal positions, with odd rows shoved half a tile east.
+const LAYOUT: OffsetLayout = OffsetLayout::OddRows;
+
+const TILE_SIZE: f64 = 10.0; // center to corner, in SVG units
+const MARGIN: f64 = 20.0;
+const CAPTION_HEIGHT: f64 = 24.0;
+const BLACK_TILE_COLOR: &str = "#202028";
+const WHITE_TILE_COLOR: &str = "#f4f1ea";
+
+// The smallest rectangle of offset coordinates containing a set of tiles. Every frame of
+// an animation uses the same bounds, so the floor doesn't jump around between frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min_column: i64,
+    pub max_column: i64,
+    pub min_row: i64,
+    pub max_row: i64,
+}
+
+impl Bounds {
+    pub fn around<'a>(tiles: impl IntoIterator<Item = &'a Axial>) -> Option<Bounds> {
+        tiles.into_iter().fold(None, |bounds, tile| {
+            let Offset { column, row } = tile.to_offset(LAYOUT);
+            Some(match bounds {
+                None => Bounds {
+                    min_column: column,
+                    max_column: column,
+                    min_row: row,
+                    max_row: row,
+                },
+                Some(bounds) => Bounds {
+                    min_column: bounds.min_column.min(column),
+                    max_column: bounds.max_column.max(column),
+                    min_row: bounds.min_row.min(row),
+                    max_row: bounds.max_row.max(row),
+                },
+            })
+        })
+    }
+
+    pub fn columns(&self) -> i64 {
+        self.max_column - self.min_column + 1
+    }
+
+    pub fn rows(&self) -> i64 {
+        self.max_row - self.min_row + 1
+    }
+}
+
+// One line per row, with odd rows indented by a character to line up the hexes:
+//  . # .
+// # . .
+pub fn render_ascii(black_tiles: &HashSet<Axial>, bounds: &Bounds) -> String {
+    let mut lines = Vec::new();
+    for row in bounds.min_row..=bounds.max_row {
+        let cells: Vec<&str> = (bounds.min_column..=bounds.max_column)
+            .map(|column| {
+                let tile = Offset::new(column, row).to_axial(LAYOUT);
+                if black_tiles.contains(&tile) {
+                    "#"
+                } else {
+                    "."
+                }
+            })
+            .collect();
+        let indent = if row & 1 == 1 { " " } else { "" };
+        lines.push(format!("{}{}", indent, cells.join(" ")));
+    }
+    lines.join("\n")
+}
+
+fn tile_center(offset: Offset, bounds: &Bounds) -> (f64, f64) {
+    let width = 3f64.sqrt() * TILE_SIZE;
+    let column = (offset.column - bounds.min_column) as f64 + 0.5 * (offset.row & 1) as f64;
+    let row = (offset.row - bounds.min_row) as f64;
+    (
+        MARGIN + width * (column + 0.5),
+        MARGIN + CAPTION_HEIGHT + TILE_SIZE * (1.5 * row + 1.0),
+    )
+}
+
+fn hexagon_points((x, y): (f64, f64)) -> String {
+    // pointy-topped, so the corners are at 30 degrees past every multiple of 60
+    let corners: Vec<String> = (0..6)
+        .map(|corner| {
+            let angle = (60.0 * corner as f64 - 30.0).to_radians();
+            format!(
+                "{:.2},{:.2}",
+                x + TILE_SIZE * angle.cos(),
+                y + TILE_SIZE * angle.sin()
+            )
+        })
+        .collect();
+    corners.join(" ")
+}
+
+pub fn render_svg(black_tiles: &HashSet<Axial>, bounds: &Bounds, caption: &str) -> String {
+    let width = 2.0 * MARGIN + 3f64.sqrt() * TILE_SIZE * (bounds.columns() as f64 + 0.5);
+    let height = 2.0 * MARGIN + CAPTION_HEIGHT + TILE_SIZE * (1.5 * bounds.rows() as f64 + 0.5);
+
+    let mut lines = vec![
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" viewBox="0 0 {:.0} {:.0}">"#,
+            width, height, width, height
+        ),
+        format!(
+            r#"<rect width="100%" height="100%" fill="{}"/>"#,
+            WHITE_TILE_COLOR
+        ),
+        format!(
+            r#"<text x="{}" y="{}" font-family="sans-serif" font-size="16">{}</text>"#,
+            MARGIN,
+            MARGIN + 16.0,
+            caption
+        ),
+        format!(r#"<g fill="{}">"#, BLACK_TILE_COLOR),
+    ];
+
+    // sorted, so the same floor always renders to the same file
+    let mut tiles: Vec<Offset> = black_tiles
+        .iter()
+        .map(|tile| tile.to_offset(LAYOUT))
+        .collect();
+    tiles.sort_unstable_by_key(|offset| (offset.row, offset.column));
+    for offset in tiles {
+        lines.push(format!(
+            r#"<polygon points="{}"/>"#,
+            hexagon_points(tile_center(offset, bounds))
+        ));
+    }
+
+    lines.push(String::from("</g>"));
+    lines.push(String::from("</svg>"));
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use hexgrid::{Axial, HexDirection};
+
+    use super::{render_ascii, render_svg, Bounds};
+
+    #[test]
+    fn test_render_ascii() {
+        // a tile and its east, north-east and south-east neighbors
+        let black_tiles: HashSet<Axial> = vec![
+            Axial::ORIGIN,
+            Axial::ORIGIN.neighbor(HexDirection::East),
+            Axial::ORIGIN.neighbor(HexDirection::NorthEast),
+            Axial::ORIGIN.neighbor(HexDirection::SouthEast),
+        ]
+        .into_iter()
+        .collect();
+        let bounds = Bounds::around(&black_tiles).unwrap();
+        assert_eq!((2, 3), (bounds.columns(), bounds.rows()));
+
+        assert_eq!(" # .\n# #\n # .", render_ascii(&black_tiles, &bounds));
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let black_tiles: HashSet<Axial> = vec![Axial::new(0, 0), Axial::new(2, -1)]
+            .into_iter()
+            .collect();
+        let bounds = Bounds::around(&black_tiles).unwrap();
+        let svg = render_svg(&black_tiles, &bounds, "Day 0: 2");
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains(">Day 0: 2</text>"));
+        assert_eq!(2, svg.matches("<polygon").count());
+    }
+}
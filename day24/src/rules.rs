@@ -0,0 +1,100 @@
+use std::fmt;
+
+// Which numbers of black neighbors make a white tile flip to black (birth) and
+// keep a black tile black (survival), written like Life rules: the puzzle's are "B2/S12".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexLifeRules {
+    pub birth: [bool; 7],
+    pub survival: [bool; 7],
+}
+
+impl HexLifeRules {
+    pub const PUZZLE: HexLifeRules = HexLifeRules {
+        birth: [false, false, true, false, false, false, false],
+        survival: [false, true, true, false, false, false, false],
+    };
+
+    pub fn parse(rules: &str) -> Result<HexLifeRules, String> {
+        let parse_counts = |part: &str, prefix: char| -> Result<[bool; 7], String> {
+            let digits = part
+                .strip_prefix(prefix)
+                .ok_or_else(|| format!("expected \"{}\" in rules {}", prefix, rules))?;
+            let mut counts = [false; 7];
+            for digit in digits.chars() {
+                match digit.to_digit(10) {
+                    Some(count) if count <= 6 => counts[count as usize] = true,
+                    _ => {
+                        return Err(format!(
+                            "invalid neighbor count {} in rules {}",
+                            digit, rules
+                        ))
+                    }
+                }
+            }
+            Ok(counts)
+        };
+
+        let (birth, survival) = rules
+            .split_once('/')
+            .ok_or_else(|| format!("expected B.../S... rules, got {}", rules))?;
+        let rules = HexLifeRules {
+            birth: parse_counts(birth, 'B')?,
+            survival: parse_counts(survival, 'S')?,
+        };
+
+        // the floor is infinite, so every white tile would flip on the first day
+        if rules.birth[0] {
+            return Err(String::from(
+                "white tiles with no black neighbors can't flip",
+            ));
+        }
+        Ok(rules)
+    }
+
+    pub fn next_color(&self, is_black: bool, black_neighbors: usize) -> bool {
+        if is_black {
+            self.survival[black_neighbors]
+        } else {
+            self.birth[black_neighbors]
+        }
+    }
+}
+
+impl fmt::Display for HexLifeRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = |counts: &[bool; 7]| -> String {
+            (0..7)
+                .filter(|&count| counts[count])
+                .map(|count| count.to_string())
+                .collect()
+        };
+        write!(f, "B{}/S{}", digits(&self.birth), digits(&self.survival))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HexLifeRules;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Ok(HexLifeRules::PUZZLE), HexLifeRules::parse("B2/S12"));
+        assert_eq!("B2/S12", HexLifeRules::PUZZLE.to_string());
+        assert_eq!("B24/S", HexLifeRules::parse("B42/S").unwrap().to_string());
+
+        assert!(HexLifeRules::parse("B2S12").is_err());
+        assert!(HexLifeRules::parse("S12/B2").is_err());
+        assert!(HexLifeRules::parse("B7/S12").is_err());
+        assert!(HexLifeRules::parse("B02/S12").is_err());
+    }
+
+    #[test]
+    fn test_next_color() {
+        let rules = HexLifeRules::PUZZLE;
+        assert!(!rules.next_color(true, 0));
+        assert!(rules.next_color(true, 1));
+        assert!(!rules.next_color(true, 3));
+        assert!(rules.next_color(false, 2));
+        assert!(!rules.next_color(false, 1));
+    }
+}
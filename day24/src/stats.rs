@@ -0,0 +1,100 @@
+use std::collections::HashSet;
+
+use hexgrid::Axial;
+
+use crate::render::Bounds;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayStats {
+    pub day: usize,
+    pub black_tiles: usize,
+    pub bounds: Option<Bounds>, // in offset coordinates, like the renderings
+    pub radius: i64,            // the distance from the reference tile to the farthest black one
+}
+
+impl DayStats {
+    pub fn measure(day: usize, black_tiles: &HashSet<Axial>) -> DayStats {
+        DayStats {
+            day,
+            black_tiles: black_tiles.len(),
+            bounds: Bounds::around(black_tiles),
+            radius: black_tiles
+                .iter()
+                .map(|tile| tile.distance(Axial::ORIGIN))
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self.bounds {
+            None => format!("day {}: no black tiles", self.day),
+            Some(bounds) => format!(
+                "day {}: {} black tiles in columns {} to {} and rows {} to {} ({}x{}), at most {} steps from the reference tile",
+                self.day,
+                self.black_tiles,
+                bounds.min_column,
+                bounds.max_column,
+                bounds.min_row,
+                bounds.max_row,
+                bounds.columns(),
+                bounds.rows(),
+                self.radius
+            ),
+        }
+    }
+}
+
+// One row per day, with empty bounding box columns on days without black tiles.
+pub fn to_csv(series: &[DayStats]) -> String {
+    let mut lines = vec![String::from(
+        "day,black_tiles,min_column,max_column,min_row,max_row,width,height,radius",
+    )];
+    for stats in series {
+        let bounds = match stats.bounds {
+            None => String::from(",,,,,"),
+            Some(bounds) => format!(
+                "{},{},{},{},{},{}",
+                bounds.min_column,
+                bounds.max_column,
+                bounds.min_row,
+                bounds.max_row,
+                bounds.columns(),
+                bounds.rows()
+            ),
+        };
+        lines.push(format!(
+            "{},{},{},{}",
+            stats.day, stats.black_tiles, bounds, stats.radius
+        ));
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use hexgrid::Axial;
+
+    use super::{to_csv, DayStats};
+
+    #[test]
+    fn test_csv() {
+        let black_tiles: HashSet<Axial> = vec![Axial::new(0, 0), Axial::new(3, -2)]
+            .into_iter()
+            .collect();
+        let series = vec![
+            DayStats::measure(0, &black_tiles),
+            DayStats::measure(1, &HashSet::new()),
+        ];
+        assert_eq!(3, series[0].radius);
+
+        assert_eq!(
+            "day,black_tiles,min_column,max_column,min_row,max_row,width,height,radius\n\
+             0,2,0,2,-2,0,3,3,3\n\
+             1,0,,,,,,,0\n",
+            to_csv(&series)
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use numtheory::{chinese_remainder, euler_totient, factorize, mod_inverse, mul_mod, pow_mod};
+
+fn inverse(a: u64, modulus: u64) -> Option<u64> {
+    mod_inverse(a as i128, modulus as i128).map(|inverse| inverse as u64)
+}
+
+// The smallest positive k such that base^k == 1, or None if base isn't invertible.
+pub fn multiplicative_order(base: u64, modulus: u64) -> Option<u64> {
+    if modulus == 1 {
+        return Some(1);
+    }
+    inverse(base, modulus)?;
+
+    // the order divides the size of the group, so take out every prime factor we can
+    let mut order = euler_totient(modulus);
+    for (prime, _) in factorize(order) {
+        while order.is_multiple_of(prime) && pow_mod(base, order / prime, modulus) == 1 {
+            order /= prime;
+        }
+    }
+    Some(order)
+}
+
+// The most baby steps to keep in memory, a few hundred megabytes. That covers orders
+// up to about 2^48.
+pub const MAX_BABY_STEPS: u64 = 1 << 24;
+
+// Finds x in 0..order with base^x == target, where order is a multiple of the order of
+// base, using about sqrt(order) multiplications and as many table entries. Orders needing
+// more than MAX_BABY_STEPS entries are an error rather than an attempt to allocate them.
+pub fn baby_step_giant_step(
+    base: u64,
+    target: u64,
+    order: u64,
+    modulus: u64,
+) -> Result<Option<u64>, String> {
+    let steps = (order as f64).sqrt().ceil() as u64 + 1;
+    if steps > MAX_BABY_STEPS {
+        return Err(format!(
+            "a discrete log in a group of order {} needs {} baby steps, more than the limit of {}",
+            order, steps, MAX_BABY_STEPS
+        ));
+    }
+    let target = target % modulus;
+
+    // baby steps: base^j for every j below the giant step size, keeping the smallest j
+    let mut baby_steps: HashMap<u64, u64> = HashMap::with_capacity(steps as usize);
+    let mut value = 1 % modulus;
+    for j in 0..steps {
+        baby_steps.entry(value).or_insert(j);
+        value = mul_mod(value, base, modulus);
+    }
+
+    // giant steps: target * base^(-steps * i), until it lands on a baby step
+    let giant_step = match inverse(pow_mod(base, steps, modulus), modulus) {
+        Some(giant_step) => giant_step,
+        None => return Ok(None),
+    };
+    let mut value = target;
+    for i in 0..steps {
+        if let Some(&j) = baby_steps.get(&value) {
+            return Ok(Some(i * steps + j));
+        }
+        value = mul_mod(value, giant_step, modulus);
+    }
+    Ok(None)
+}
+
+// The smallest x >= 0 with base^x == target (mod modulus), if there is one. The base
+// must be coprime to the modulus. It's an error if the order of the base has a prime
+// factor too large for baby-step giant-step.
+//
+// Pohlig-Hellman: for every prime power p^e dividing the order of base, the exponent
+// modulo p^e is found one base-p digit at a time, with baby-step giant-step in the
+// subgroup of order p. That takes about sqrt(p) work per digit instead of sqrt(order)
+// overall, so it's fast whenever the order has no large prime factors.
+pub fn discrete_log(base: u64, target: u64, modulus: u64) -> Result<Option<u64>, String> {
+    let order = match multiplicative_order(base, modulus) {
+        Some(order) => order,
+        None => return Ok(None),
+    };
+    let target = target % modulus;
+
+    let mut residues = vec![];
+    for (prime, exponent) in factorize(order) {
+        let prime_power = prime.pow(exponent);
+        let cofactor = order / prime_power;
+
+        // base and target projected into the subgroup of order prime^exponent
+        let sub_base = pow_mod(base, cofactor, modulus);
+        let sub_target = pow_mod(target, cofactor, modulus);
+        let sub_base_inverse = inverse(sub_base, modulus).unwrap(); // the base is invertible
+        let digit_base = pow_mod(sub_base, prime_power / prime, modulus); // of order prime
+
+        let mut x = 0;
+        let mut digit_place = 1;
+        for k in 0..exponent {
+            // strip off the digits found so far, then look at the next one
+            let remainder = mul_mod(sub_target, pow_mod(sub_base_inverse, x, modulus), modulus);
+            let digit_target = pow_mod(remainder, prime.pow(exponent - 1 - k), modulus);
+            let digit = match baby_step_giant_step(digit_base, digit_target, prime, modulus)? {
+                Some(digit) => digit,
+                None => return Ok(None), // target isn't a power of base
+            };
+            x += digit * digit_place;
+            digit_place *= prime;
+        }
+        residues.push((x as i128, prime_power as i128));
+    }
+
+    let (x, _) = chinese_remainder(&residues).unwrap();
+    let x = x as u64;
+    if pow_mod(base, x, modulus) == target {
+        Ok(Some(x))
+    } else {
+        Ok(None) // target isn't a power of base
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use numtheory::pow_mod;
+
+    use super::{baby_step_giant_step, discrete_log, multiplicative_order};
+
+    fn brute_force_log(base: u64, target: u64, modulus: u64) -> Option<u64> {
+        let mut value = 1 % modulus;
+        for x in 0..modulus {
+            if value == target {
+                return Some(x);
+            }
+            value = value * base % modulus;
+        }
+        None
+    }
+
+    #[test]
+    fn test_multiplicative_order() {
+        assert_eq!(Some(20201226), multiplicative_order(7, 20201227));
+        assert_eq!(Some(6), multiplicative_order(3, 7));
+        assert_eq!(Some(2), multiplicative_order(5, 8));
+        assert_eq!(None, multiplicative_order(4, 8));
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        for &modulus in [2u64, 7, 8, 15, 97, 101, 243, 1000].iter() {
+            for base in 1..modulus.min(30) {
+                if multiplicative_order(base, modulus).is_none() {
+                    assert_eq!(Ok(None), discrete_log(base, 1, modulus));
+                    continue;
+                }
+                for target in 0..modulus {
+                    assert_eq!(
+                        Ok(brute_force_log(base, target, modulus)),
+                        discrete_log(base, target, modulus),
+                        "log base {} of {} mod {}",
+                        base,
+                        target,
+                        modulus
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_moduli() {
+        // the puzzle's sample keys
+        assert_eq!(Ok(Some(8)), discrete_log(7, 5764801, 20201227));
+        assert_eq!(Ok(Some(11)), discrete_log(7, 17807724, 20201227));
+
+        // 2^61 - 1 is prime, and 2^61 - 2 only has small prime factors
+        let modulus = (1u64 << 61) - 1;
+        let secret = 1_234_567_890_123_456_789;
+        let target = pow_mod(37, secret, modulus);
+        let x = discrete_log(37, target, modulus).unwrap().unwrap();
+        assert_eq!(target, pow_mod(37, x, modulus));
+        assert_eq!(0, (secret - x) % multiplicative_order(37, modulus).unwrap());
+
+        // 10^9 + 7 - 1 = 2 * 500000003, so this is mostly one baby-step giant-step
+        let modulus = 1_000_000_007;
+        let target = pow_mod(5, 987_654_321, modulus);
+        assert_eq!(Ok(Some(987_654_321)), discrete_log(5, target, modulus));
+        assert_eq!(
+            Ok(Some(987_654_321)),
+            baby_step_giant_step(5, target, modulus - 1, modulus)
+        );
+    }
+
+    #[test]
+    fn test_orders_too_large_for_baby_steps() {
+        assert!(baby_step_giant_step(3, 5, 1 << 60, (1 << 61) - 1).is_err());
+
+        // a safe prime: its order minus one is twice a prime near 2^61
+        let modulus = (1u64 << 62) + 6595;
+        let target = pow_mod(3, 123_456_789, modulus);
+        assert!(discrete_log(3, target, modulus).is_err());
+    }
+}
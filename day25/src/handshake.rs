@@ -0,0 +1,166 @@
+use numtheory::{euler_totient, pow_mod, Rng};
+
+use crate::discrete_log::{discrete_log, multiplicative_order};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyPair {
+    pub loop_size: u64, // the secret
+    pub public_key: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrackedHandshake {
+    pub card_loop_size: u64,
+    pub door_loop_size: u64,
+    pub encryption_key: u64,
+}
+
+// The puzzle's key exchange, which is Diffie-Hellman: transforming the subject number
+// with a loop size is raising it to that power modulo the modulus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handshake {
+    pub subject_number: u64,
+    pub modulus: u64,
+}
+
+impl Handshake {
+    pub const PUZZLE: Handshake = Handshake {
+        subject_number: 7,
+        modulus: 20201227,
+    };
+
+    pub fn transform(&self, subject_number: u64, loop_size: u64) -> u64 {
+        pow_mod(subject_number, loop_size, self.modulus)
+    }
+
+    // A generator's powers cover every number coprime to the modulus, so every public key
+    // is possible and a loop size can't be narrowed down any further than by cracking it.
+    pub fn check_generator(&self) -> Result<(), String> {
+        let group_size = euler_totient(self.modulus);
+        match multiplicative_order(self.subject_number, self.modulus) {
+            None => Err(format!(
+                "subject number {} isn't coprime to the modulus {}",
+                self.subject_number, self.modulus
+            )),
+            Some(order) if order != group_size => Err(format!(
+                "subject number {} only generates {} of the {} numbers coprime to the modulus {}",
+                self.subject_number, order, group_size, self.modulus
+            )),
+            Some(_) => Ok(()),
+        }
+    }
+
+    // Picks a loop size at random among those giving distinct public keys.
+    pub fn generate_key_pair(&self, rng: &mut Rng) -> KeyPair {
+        let order = multiplicative_order(self.subject_number, self.modulus)
+            .expect("the subject number must be coprime to the modulus");
+        let loop_size = if order > 1 {
+            1 + rng.below(order - 1)
+        } else {
+            1
+        };
+        KeyPair {
+            loop_size,
+            public_key: self.transform(self.subject_number, loop_size),
+        }
+    }
+
+    // Finds the smallest loop sizes that produce the public keys, and the encryption key
+    // that both sides compute from them.
+    pub fn crack(
+        &self,
+        card_public_key: u64,
+        door_public_key: u64,
+    ) -> Result<CrackedHandshake, String> {
+        let card_loop_size = self.find_loop_size(card_public_key)?;
+        let door_loop_size = self.find_loop_size(door_public_key)?;
+
+        let card_calculated_key = self.transform(door_public_key, card_loop_size);
+        let door_calculated_key = self.transform(card_public_key, door_loop_size);
+        assert_eq!(card_calculated_key, door_calculated_key);
+
+        Ok(CrackedHandshake {
+            card_loop_size,
+            door_loop_size,
+            encryption_key: card_calculated_key,
+        })
+    }
+
+    fn find_loop_size(&self, public_key: u64) -> Result<u64, String> {
+        discrete_log(self.subject_number, public_key, self.modulus)?.ok_or_else(|| {
+            format!(
+                "public key {} isn't a power of the subject number {}",
+                public_key, self.subject_number
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use numtheory::Rng;
+
+    use super::Handshake;
+
+    #[test]
+    fn test_sample() {
+        let cracked = Handshake::PUZZLE.crack(5764801, 17807724).unwrap();
+        assert_eq!((8, 11), (cracked.card_loop_size, cracked.door_loop_size));
+        assert_eq!(14897079, cracked.encryption_key);
+    }
+
+    #[test]
+    fn test_check_generator() {
+        assert_eq!(Ok(()), Handshake::PUZZLE.check_generator());
+
+        // 2 has order 11 modulo 23, so it only reaches half of the possible keys
+        let handshake = Handshake {
+            subject_number: 2,
+            modulus: 23,
+        };
+        assert!(handshake.check_generator().is_err());
+        assert_eq!(
+            Err(String::from(
+                "public key 5 isn't a power of the subject number 2"
+            )),
+            handshake.crack(5, 4)
+        );
+        let handshake = Handshake {
+            subject_number: 6,
+            modulus: 15,
+        };
+        assert!(handshake.check_generator().is_err());
+    }
+
+    #[test]
+    fn test_generate_and_crack() {
+        let mut rng = Rng::new(25);
+        let handshakes = [
+            Handshake::PUZZLE,
+            Handshake {
+                subject_number: 3,
+                modulus: (1 << 61) - 1,
+            },
+            Handshake {
+                subject_number: 2,
+                modulus: 23,
+            },
+        ];
+        for handshake in handshakes.iter() {
+            for _ in 0..10 {
+                let card = handshake.generate_key_pair(&mut rng);
+                let door = handshake.generate_key_pair(&mut rng);
+                let cracked = handshake.crack(card.public_key, door.public_key).unwrap();
+
+                assert_eq!(
+                    handshake.transform(card.public_key, door.loop_size),
+                    cracked.encryption_key
+                );
+                assert_eq!(
+                    card.public_key,
+                    handshake.transform(handshake.subject_number, cracked.card_loop_size)
+                );
+            }
+        }
+    }
+}
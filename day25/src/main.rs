@@ -1,64 +1,135 @@
-use std::fs;
+use std::{env, fs};
 
-fn main() {
-    let contents = fs::read_to_string(
-        "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day25/input.txt",
-    )
-    .unwrap();
-
-    let subject_number: i64 = 7;
-    let modulus: i64 = 20201227;
-    let lines: Vec<i64> = contents
+use handshake::Handshake;
+use numtheory::Rng;
+
+mod discrete_log;
+mod handshake;
+
+#[derive(Debug)]
+struct Options {
+    input_path: String,
+    handshake: Handshake,
+    generate: bool,
+    seed: u64,
+    output_path: Option<String>,
+}
+
+fn parse_options(args: impl Iterator<Item = String>) -> Options {
+    let mut options = Options {
+        input_path: String::from(
+            "/mnt/c/Users/predrag/Dropbox/Documents/Code/advent-of-code-2020/day25/input.txt",
+        ),
+        handshake: Handshake::PUZZLE,
+        generate: false,
+        seed: 2020,
+        output_path: None,
+    };
+
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--subject" => {
+                options.handshake.subject_number = args
+                    .next()
+                    .expect("missing subject number")
+                    .parse()
+                    .unwrap()
+            }
+            "--modulus" => {
+                options.handshake.modulus = args.next().expect("missing modulus").parse().unwrap()
+            }
+            "--generate" => options.generate = true,
+            "--seed" => options.seed = args.next().expect("missing seed").parse().unwrap(),
+            "--output" => options.output_path = Some(args.next().expect("missing output path")),
+            _ if arg.starts_with("--") => panic!("unrecognized option: {}", arg),
+            _ => options.input_path = arg,
+        }
+    }
+
+    options
+}
+
+fn parse_public_keys(contents: &str) -> (u64, u64) {
+    let lines: Vec<u64> = contents
         .trim()
         .split('\n')
         .map(|x| x.parse().unwrap())
         .collect();
-    let card_pubkey = lines[0];
-    let door_pubkey = lines[1];
+    assert_eq!(lines.len(), 2);
+    (lines[0], lines[1])
+}
+
+// Makes up a card and a door key pair, then checks that cracking their public keys
+// recovers the encryption key.
+fn generate_handshake(handshake: &Handshake, seed: u64, output_path: &Option<String>) {
+    let mut rng = Rng::new(seed);
+    let card = handshake.generate_key_pair(&mut rng);
+    let door = handshake.generate_key_pair(&mut rng);
+    let encryption_key = handshake.transform(door.public_key, card.loop_size);
 
     println!(
-        "{}",
-        solve_part1(subject_number, modulus, card_pubkey, door_pubkey)
+        "card: loop size {}, public key {}",
+        card.loop_size, card.public_key
     );
-}
+    println!(
+        "door: loop size {}, public key {}",
+        door.loop_size, door.public_key
+    );
+    println!("encryption key: {}", encryption_key);
 
-fn calculate_powmod(base: i64, power: i64, modulus: i64) -> i64 {
-    let mut value = 1i64;
-    for _ in 0..power {
-        value = (value * base) % modulus;
+    if let Some(output_path) = output_path {
+        let contents = format!("{}\n{}\n", card.public_key, door.public_key);
+        fs::write(output_path, contents).unwrap();
     }
-    value
-}
 
-fn calculate_discrete_log(base: i64, modulus: i64, powmod: i64) -> i64 {
-    let mut value = 1i64;
-    for discrete_log in 0..modulus {
-        if value == powmod {
-            return discrete_log;
+    match handshake.crack(card.public_key, door.public_key) {
+        Ok(cracked) => {
+            println!(
+                "cracked: card loop size {}, door loop size {}, encryption key {}",
+                cracked.card_loop_size, cracked.door_loop_size, cracked.encryption_key
+            );
+            assert_eq!(encryption_key, cracked.encryption_key);
         }
-        value = (value * base) % modulus;
+        Err(message) => println!("can't crack: {}", message),
     }
-
-    unreachable!()
 }
 
-fn solve_part1(subject_number: i64, modulus: i64, card_pubkey: i64, door_pubkey: i64) -> i64 {
-    let card_secret_key = calculate_discrete_log(subject_number, modulus, card_pubkey);
-    let door_secret_key = calculate_discrete_log(subject_number, modulus, door_pubkey);
+fn main() {
+    let options = parse_options(env::args().skip(1));
+    if let Err(problem) = options.handshake.check_generator() {
+        eprintln!("warning: {}", problem);
+    }
 
-    assert_eq!(
-        card_pubkey,
-        calculate_powmod(subject_number, card_secret_key, modulus)
-    );
-    assert_eq!(
-        door_pubkey,
-        calculate_powmod(subject_number, door_secret_key, modulus)
-    );
+    if options.generate {
+        generate_handshake(&options.handshake, options.seed, &options.output_path);
+        return;
+    }
 
-    let door_calculated_key = calculate_powmod(card_pubkey, door_secret_key, modulus);
-    let card_calculated_key = calculate_powmod(door_pubkey, card_secret_key, modulus);
+    let contents = fs::read_to_string(&options.input_path).unwrap();
+    let (card_pubkey, door_pubkey) = parse_public_keys(&contents);
 
-    assert_eq!(card_calculated_key, door_calculated_key);
+    match solve_part1(&options.handshake, card_pubkey, door_pubkey) {
+        Ok(encryption_key) => println!("{}", encryption_key),
+        Err(message) => println!("{}", message),
+    }
+}
 
-    card_calculated_key
+fn solve_part1(handshake: &Handshake, card_pubkey: u64, door_pubkey: u64) -> Result<u64, String> {
+    Ok(handshake.crack(card_pubkey, door_pubkey)?.encryption_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_public_keys, solve_part1};
+    use crate::handshake::Handshake;
+
+    #[test]
+    fn test_sample() {
+        let (card_pubkey, door_pubkey) = parse_public_keys(include_str!("../sample_input.txt"));
+        assert_eq!(
+            Ok(14897079),
+            solve_part1(&Handshake::PUZZLE, card_pubkey, door_pubkey)
+        );
+    }
 }
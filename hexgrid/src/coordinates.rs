@@ -0,0 +1,276 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::direction::HexDirection;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Axial {
+    pub q: i64,
+    pub r: i64,
+}
+
+// Axial coordinates plus the redundant third one, so that q + r + s == 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Cube {
+    pub q: i64,
+    pub r: i64,
+    pub s: i64,
+}
+
+// Which rows (for pointy-topped hexes) or columns (for flat-topped ones) are shoved
+// half a hex over to make a rectangular layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OffsetLayout {
+    OddRows,
+    EvenRows,
+    OddColumns,
+    EvenColumns,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Offset {
+    pub column: i64,
+    pub row: i64,
+}
+
+impl Axial {
+    pub const ORIGIN: Axial = Axial { q: 0, r: 0 };
+
+    pub fn new(q: i64, r: i64) -> Axial {
+        Axial { q, r }
+    }
+
+    pub fn to_cube(self) -> Cube {
+        Cube {
+            q: self.q,
+            r: self.r,
+            s: -self.q - self.r,
+        }
+    }
+
+    pub fn to_offset(self, layout: OffsetLayout) -> Offset {
+        let (q, r) = (self.q, self.r);
+        match layout {
+            OffsetLayout::OddRows => Offset {
+                column: q + (r - (r & 1)) / 2,
+                row: r,
+            },
+            OffsetLayout::EvenRows => Offset {
+                column: q + (r + (r & 1)) / 2,
+                row: r,
+            },
+            OffsetLayout::OddColumns => Offset {
+                column: q,
+                row: r + (q - (q & 1)) / 2,
+            },
+            OffsetLayout::EvenColumns => Offset {
+                column: q,
+                row: r + (q + (q & 1)) / 2,
+            },
+        }
+    }
+
+    pub fn neighbor(self, direction: HexDirection) -> Axial {
+        self + direction.offset()
+    }
+
+    pub fn neighbors(self) -> impl Iterator<Item = Axial> {
+        HexDirection::ALL
+            .iter()
+            .map(move |&direction| self.neighbor(direction))
+    }
+
+    // the number of steps between the two hexes
+    pub fn distance(self, other: Axial) -> i64 {
+        let difference = (self - other).to_cube();
+        difference
+            .q
+            .abs()
+            .max(difference.r.abs())
+            .max(difference.s.abs())
+    }
+
+    // Rotates around the given center by the given number of 60-degree steps;
+    // negative steps rotate counterclockwise.
+    pub fn rotate_about(self, center: Axial, steps: i64) -> Axial {
+        let mut relative = (self - center).to_cube();
+        for _ in 0..steps.rem_euclid(6) {
+            relative = Cube {
+                q: -relative.r,
+                r: -relative.s,
+                s: -relative.q,
+            };
+        }
+        center + relative.to_axial()
+    }
+}
+
+impl Cube {
+    pub fn to_axial(self) -> Axial {
+        Axial {
+            q: self.q,
+            r: self.r,
+        }
+    }
+
+    // The hex containing a point given in fractional cube coordinates.
+    pub fn round(q: f64, r: f64, s: f64) -> Cube {
+        let (mut rounded_q, mut rounded_r, mut rounded_s) = (q.round(), r.round(), s.round());
+        let (q_error, r_error, s_error) = (
+            (rounded_q - q).abs(),
+            (rounded_r - r).abs(),
+            (rounded_s - s).abs(),
+        );
+
+        // rounding may break q + r + s == 0, so fix up the coordinate that moved the most
+        if q_error > r_error && q_error > s_error {
+            rounded_q = -rounded_r - rounded_s;
+        } else if r_error > s_error {
+            rounded_r = -rounded_q - rounded_s;
+        } else {
+            rounded_s = -rounded_q - rounded_r;
+        }
+
+        Cube {
+            q: rounded_q as i64,
+            r: rounded_r as i64,
+            s: rounded_s as i64,
+        }
+    }
+}
+
+impl Offset {
+    pub fn new(column: i64, row: i64) -> Offset {
+        Offset { column, row }
+    }
+
+    pub fn to_axial(self, layout: OffsetLayout) -> Axial {
+        let (column, row) = (self.column, self.row);
+        match layout {
+            OffsetLayout::OddRows => Axial::new(column - (row - (row & 1)) / 2, row),
+            OffsetLayout::EvenRows => Axial::new(column - (row + (row & 1)) / 2, row),
+            OffsetLayout::OddColumns => Axial::new(column, row - (column - (column & 1)) / 2),
+            OffsetLayout::EvenColumns => Axial::new(column, row - (column + (column & 1)) / 2),
+        }
+    }
+}
+
+impl Add for Axial {
+    type Output = Axial;
+
+    fn add(self, other: Axial) -> Axial {
+        Axial::new(self.q + other.q, self.r + other.r)
+    }
+}
+
+impl Sub for Axial {
+    type Output = Axial;
+
+    fn sub(self, other: Axial) -> Axial {
+        Axial::new(self.q - other.q, self.r - other.r)
+    }
+}
+
+impl Neg for Axial {
+    type Output = Axial;
+
+    fn neg(self) -> Axial {
+        Axial::new(-self.q, -self.r)
+    }
+}
+
+impl Mul<i64> for Axial {
+    type Output = Axial;
+
+    fn mul(self, factor: i64) -> Axial {
+        Axial::new(self.q * factor, self.r * factor)
+    }
+}
+
+impl From<(i64, i64)> for Axial {
+    fn from((q, r): (i64, i64)) -> Axial {
+        Axial::new(q, r)
+    }
+}
+
+impl From<Axial> for (i64, i64) {
+    fn from(hex: Axial) -> (i64, i64) {
+        (hex.q, hex.r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Axial, Cube, Offset, OffsetLayout};
+    use crate::direction::HexDirection;
+
+    fn nearby_hexes() -> impl Iterator<Item = Axial> {
+        (-5..=5).flat_map(|q| (-5..=5).map(move |r| Axial::new(q, r)))
+    }
+
+    #[test]
+    fn test_conversions_round_trip() {
+        let layouts = [
+            OffsetLayout::OddRows,
+            OffsetLayout::EvenRows,
+            OffsetLayout::OddColumns,
+            OffsetLayout::EvenColumns,
+        ];
+        for hex in nearby_hexes() {
+            let cube = hex.to_cube();
+            assert_eq!(0, cube.q + cube.r + cube.s);
+            assert_eq!(hex, cube.to_axial());
+            for &layout in layouts.iter() {
+                assert_eq!(hex, hex.to_offset(layout).to_axial(layout));
+            }
+        }
+
+        // the same examples as the Red Blob Games page
+        assert_eq!(
+            Offset::new(1, -1),
+            Axial::new(2, -1).to_offset(OffsetLayout::OddRows)
+        );
+        assert_eq!(
+            Offset::new(2, -1),
+            Axial::new(2, -1).to_offset(OffsetLayout::EvenRows)
+        );
+        assert_eq!(
+            Offset::new(-1, 2),
+            Axial::new(-1, 3).to_offset(OffsetLayout::OddColumns)
+        );
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(0, Axial::ORIGIN.distance(Axial::ORIGIN));
+        assert_eq!(3, Axial::new(3, -1).distance(Axial::new(0, 0)));
+        assert_eq!(4, Axial::new(-2, 3).distance(Axial::new(1, -1)));
+        for neighbor in Axial::new(4, -7).neighbors() {
+            assert_eq!(1, neighbor.distance(Axial::new(4, -7)));
+        }
+    }
+
+    #[test]
+    fn test_rotation() {
+        let center = Axial::new(1, 2);
+        let hex = center + HexDirection::East.offset() * 2 + HexDirection::SouthEast.offset();
+        let rotated = hex.rotate_about(center, 1);
+        assert_eq!(
+            center + HexDirection::SouthEast.offset() * 2 + HexDirection::SouthWest.offset(),
+            rotated
+        );
+        assert_eq!(hex, rotated.rotate_about(center, -1));
+        assert_eq!(hex, hex.rotate_about(center, 6));
+        for hex in nearby_hexes() {
+            assert_eq!(
+                hex.distance(center),
+                hex.rotate_about(center, 2).distance(center)
+            );
+        }
+    }
+
+    #[test]
+    fn test_round() {
+        assert_eq!(Cube { q: 1, r: -1, s: 0 }, Cube::round(0.9, -1.2, 0.3));
+        assert_eq!(Cube { q: 0, r: 0, s: 0 }, Cube::round(0.3, 0.3, -0.6));
+    }
+}
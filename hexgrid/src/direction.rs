@@ -0,0 +1,116 @@
+use crate::coordinates::Axial;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HexDirection {
+    East,
+    SouthEast,
+    SouthWest,
+    West,
+    NorthWest,
+    NorthEast,
+}
+
+impl HexDirection {
+    // in clockwise order, starting from the east
+    pub const ALL: [HexDirection; 6] = [
+        HexDirection::East,
+        HexDirection::SouthEast,
+        HexDirection::SouthWest,
+        HexDirection::West,
+        HexDirection::NorthWest,
+        HexDirection::NorthEast,
+    ];
+
+    pub fn offset(self) -> Axial {
+        match self {
+            HexDirection::East => Axial::new(1, 0),
+            HexDirection::West => Axial::new(-1, 0),
+            HexDirection::SouthEast => Axial::new(0, 1),
+            HexDirection::NorthWest => Axial::new(0, -1),
+            HexDirection::SouthWest => Axial::new(-1, 1),
+            HexDirection::NorthEast => Axial::new(1, -1),
+        }
+    }
+
+    fn index(self) -> usize {
+        HexDirection::ALL
+            .iter()
+            .position(|&direction| direction == self)
+            .unwrap()
+    }
+
+    // Turns by the given number of 60-degree steps; negative steps turn counterclockwise.
+    pub fn rotate_clockwise(self, steps: i64) -> HexDirection {
+        HexDirection::ALL[(self.index() as i64 + steps).rem_euclid(6) as usize]
+    }
+
+    pub fn opposite(self) -> HexDirection {
+        self.rotate_clockwise(3)
+    }
+
+    // The compass abbreviations used by Advent of Code: "e", "se", "sw", "w", "nw" and "ne".
+    pub fn name(self) -> &'static str {
+        match self {
+            HexDirection::East => "e",
+            HexDirection::SouthEast => "se",
+            HexDirection::SouthWest => "sw",
+            HexDirection::West => "w",
+            HexDirection::NorthWest => "nw",
+            HexDirection::NorthEast => "ne",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<HexDirection> {
+        HexDirection::ALL
+            .iter()
+            .copied()
+            .find(|direction| direction.name() == name)
+    }
+
+    // Splits the direction at the start of a string like "esenee" off the rest of it.
+    pub fn parse_prefix(text: &str) -> Option<(HexDirection, &str)> {
+        let length = if text.starts_with(['n', 's']) { 2 } else { 1 };
+        let direction = HexDirection::from_name(text.get(..length)?)?;
+        Some((direction, &text[length..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HexDirection;
+    use crate::coordinates::Axial;
+
+    #[test]
+    fn test_rotation() {
+        assert_eq!(
+            HexDirection::SouthWest,
+            HexDirection::East.rotate_clockwise(2)
+        );
+        assert_eq!(
+            HexDirection::NorthEast,
+            HexDirection::East.rotate_clockwise(-1)
+        );
+        for direction in HexDirection::ALL.iter().copied() {
+            assert_eq!(
+                Axial::new(0, 0),
+                direction.offset() + direction.opposite().offset()
+            );
+            assert_eq!(direction, direction.rotate_clockwise(12));
+        }
+    }
+
+    #[test]
+    fn test_parse_prefix() {
+        assert_eq!(
+            Some((HexDirection::SouthEast, "nee")),
+            HexDirection::parse_prefix("senee")
+        );
+        assert_eq!(
+            Some((HexDirection::East, "")),
+            HexDirection::parse_prefix("e")
+        );
+        assert_eq!(None, HexDirection::parse_prefix(""));
+        assert_eq!(None, HexDirection::parse_prefix("n"));
+        assert_eq!(None, HexDirection::parse_prefix("x"));
+    }
+}
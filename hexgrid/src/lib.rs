@@ -0,0 +1,12 @@
+// Hexagonal grids, following https://www.redblobgames.com/grids/hexagons/
+//
+// Hexes are pointy-topped, with neighbors to the east and west and rows running
+// west to east. Axial coordinates are the canonical form: q grows to the east and
+// r grows to the south-east, so moving east is (+1, 0) and south-east is (0, +1).
+mod coordinates;
+mod direction;
+mod shapes;
+
+pub use coordinates::{Axial, Cube, Offset, OffsetLayout};
+pub use direction::HexDirection;
+pub use shapes::{line, ring, spiral};
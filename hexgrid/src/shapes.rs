@@ -0,0 +1,119 @@
+use crate::{coordinates::Cube, direction::HexDirection, Axial};
+
+// The hexes at exactly the given distance from the center, clockwise from the
+// west-most one.
+pub fn ring(center: Axial, radius: i64) -> Vec<Axial> {
+    assert!(radius >= 0);
+    if radius == 0 {
+        return vec![center];
+    }
+
+    let mut result = Vec::with_capacity(6 * radius as usize);
+    let mut hex = center + HexDirection::West.offset() * radius;
+    for &direction in HexDirection::ALL.iter() {
+        // the sides run north-east from the west-most hex, then east, south-east and so on
+        let side = direction.rotate_clockwise(-1);
+        for _ in 0..radius {
+            result.push(hex);
+            hex = hex.neighbor(side);
+        }
+    }
+    result
+}
+
+// Every hex within the given distance of the center, ring by ring outward.
+pub fn spiral(center: Axial, radius: i64) -> Vec<Axial> {
+    (0..=radius)
+        .flat_map(|ring_radius| ring(center, ring_radius))
+        .collect()
+}
+
+// The hexes on a straight line between the two, both ends included.
+pub fn line(from: Axial, to: Axial) -> Vec<Axial> {
+    let steps = from.distance(to);
+    if steps == 0 {
+        return vec![from];
+    }
+
+    // nudge the line off the edges between hexes so ties always round the same way
+    let (from, to) = (from.to_cube(), to.to_cube());
+    let start = (
+        from.q as f64 + 1e-6,
+        from.r as f64 + 2e-6,
+        from.s as f64 - 3e-6,
+    );
+    let lerp = |a: f64, b: i64, t: f64| a + (b as f64 - a) * t;
+
+    (0..=steps)
+        .map(|step| {
+            let t = step as f64 / steps as f64;
+            Cube::round(
+                lerp(start.0, to.q, t),
+                lerp(start.1, to.r, t),
+                lerp(start.2, to.s, t),
+            )
+            .to_axial()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{line, ring, spiral};
+    use crate::Axial;
+
+    #[test]
+    fn test_ring() {
+        let center = Axial::new(2, -3);
+        assert_eq!(vec![center], ring(center, 0));
+        for radius in 1..5 {
+            let hexes = ring(center, radius);
+            assert_eq!(6 * radius as usize, hexes.len());
+            assert!(hexes.iter().all(|hex| hex.distance(center) == radius));
+            assert_eq!(hexes.len(), hexes.iter().collect::<HashSet<_>>().len());
+
+            // consecutive hexes are neighbors, all the way around
+            for (index, hex) in hexes.iter().enumerate() {
+                assert_eq!(1, hex.distance(hexes[(index + 1) % hexes.len()]));
+            }
+        }
+        assert_eq!(
+            vec![
+                Axial::new(-1, 0),
+                Axial::new(0, -1),
+                Axial::new(1, -1),
+                Axial::new(1, 0),
+                Axial::new(0, 1),
+                Axial::new(-1, 1)
+            ],
+            ring(Axial::ORIGIN, 1)
+        );
+    }
+
+    #[test]
+    fn test_spiral() {
+        let hexes = spiral(Axial::ORIGIN, 3);
+        assert_eq!(37, hexes.len());
+        assert_eq!(Axial::ORIGIN, hexes[0]);
+        assert_eq!(37, hexes.iter().collect::<HashSet<_>>().len());
+    }
+
+    #[test]
+    fn test_line() {
+        assert_eq!(
+            vec![Axial::new(1, 1)],
+            line(Axial::new(1, 1), Axial::new(1, 1))
+        );
+
+        let from = Axial::new(-2, 1);
+        let to = Axial::new(3, -2);
+        let hexes = line(from, to);
+        assert_eq!(6, hexes.len());
+        assert_eq!((from, to), (hexes[0], hexes[5]));
+        for pair in hexes.windows(2) {
+            assert_eq!(1, pair[0].distance(pair[1]));
+        }
+    }
+}
@@ -0,0 +1,148 @@
+// Always non-negative, with gcd(0, 0) == 0.
+pub fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+pub fn lcm(a: i128, b: i128) -> i128 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    (a / gcd(a, b) * b).abs()
+}
+
+// Returns (g, x, y) with a * x + b * y == g == gcd(a, b).
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_x, x) = (x, old_x - quotient * x);
+        (old_y, y) = (y, old_y - quotient * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+// The x in 0..modulus with a * x == 1 (mod modulus), if a and the modulus are coprime.
+pub fn mod_inverse(a: i128, modulus: i128) -> Option<i128> {
+    assert!(modulus > 0);
+    let (g, x, _) = extended_gcd(a.rem_euclid(modulus), modulus);
+    if g == 1 {
+        Some(x.rem_euclid(modulus))
+    } else {
+        None
+    }
+}
+
+// Solves x == residue (mod modulus) for every (residue, modulus) pair. The moduli needn't
+// be coprime: returns the smallest non-negative x and the lcm of the moduli, or None if
+// the congruences contradict each other.
+pub fn chinese_remainder(congruences: &[(i128, i128)]) -> Option<(i128, i128)> {
+    congruences
+        .iter()
+        .try_fold((0, 1), |(x, combined_modulus), &(residue, modulus)| {
+            assert!(modulus > 0);
+            // x + combined_modulus * t == residue (mod modulus)
+            let (g, inverse, _) = extended_gcd(combined_modulus, modulus);
+            let difference = residue - x;
+            if difference % g != 0 {
+                return None;
+            }
+
+            let step = modulus / g;
+            let t = (difference / g).rem_euclid(step) * inverse.rem_euclid(step) % step;
+            let next_modulus = combined_modulus * step;
+            Some((
+                (x + combined_modulus * t).rem_euclid(next_modulus),
+                next_modulus,
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chinese_remainder, extended_gcd, gcd, lcm, mod_inverse};
+    use crate::Rng;
+
+    #[test]
+    fn test_gcd_and_lcm() {
+        assert_eq!(6, gcd(54, 24));
+        assert_eq!(6, gcd(-54, 24));
+        assert_eq!(7, gcd(0, 7));
+        assert_eq!(0, gcd(0, 0));
+        assert_eq!(216, lcm(54, 24));
+        assert_eq!(0, lcm(0, 24));
+    }
+
+    #[test]
+    fn test_extended_gcd_properties() {
+        let mut rng = Rng::new(13);
+        for _ in 0..2000 {
+            let a = rng.up_to(u64::MAX) as i128 * if rng.next_u64() & 1 == 0 { 1 } else { -1 };
+            let b = rng.up_to(u64::MAX) as i128;
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(g, gcd(a, b));
+            assert_eq!(g, a * x + b * y);
+            assert_eq!(0, a % g);
+            assert_eq!(0, b % g);
+
+            match mod_inverse(a, b) {
+                Some(inverse) => {
+                    assert_eq!(1, g);
+                    assert!((0..b).contains(&inverse));
+                    let product = a.rem_euclid(b) as u128 * inverse as u128;
+                    assert_eq!(1 % b as u128, product % b as u128);
+                }
+                None => assert!(g != 1),
+            }
+        }
+    }
+
+    #[test]
+    fn test_chinese_remainder() {
+        // the day 13 sample: 7,13,x,x,59,x,31,19
+        let buses = [(0, 7), (1, 13), (4, 59), (6, 31), (7, 19)];
+        let congruences: Vec<(i128, i128)> =
+            buses.iter().map(|&(offset, id)| (-offset, id)).collect();
+        assert_eq!(
+            Some((1068781, 7 * 13 * 59 * 31 * 19)),
+            chinese_remainder(&congruences)
+        );
+
+        // moduli with common factors
+        assert_eq!(Some((10, 12)), chinese_remainder(&[(4, 6), (2, 4)]));
+        assert_eq!(None, chinese_remainder(&[(1, 6), (2, 4)]));
+        assert_eq!(Some((0, 1)), chinese_remainder(&[]));
+    }
+
+    #[test]
+    fn test_chinese_remainder_properties() {
+        let mut rng = Rng::new(2020);
+        for _ in 0..2000 {
+            let count = rng.up_to(4) as usize;
+            let solution = rng.up_to(1 << 40) as i128;
+            let congruences: Vec<(i128, i128)> = (0..count)
+                .map(|_| {
+                    let modulus = rng.up_to(1 << 20) as i128;
+                    (solution % modulus, modulus)
+                })
+                .collect();
+
+            let (x, modulus) = chinese_remainder(&congruences).unwrap();
+            let expected_modulus = congruences
+                .iter()
+                .fold(1, |combined, &(_, modulus)| lcm(combined, modulus));
+            assert_eq!(expected_modulus, modulus);
+            assert_eq!(solution % modulus, x);
+        }
+    }
+}
@@ -0,0 +1,13 @@
+// Number theory for puzzles: modular arithmetic on u64 (with u128 intermediates, so any
+// 64-bit modulus works), and gcd and Chinese remaindering on i128, which holds any u64
+// as well as the negative coefficients that extended Euclid produces. Also the random
+// number generator that the puzzles' generators and property tests share.
+mod euclid;
+mod modular;
+mod primes;
+mod random;
+
+pub use euclid::{chinese_remainder, extended_gcd, gcd, lcm, mod_inverse};
+pub use modular::{mul_mod, pow_mod};
+pub use primes::{euler_totient, factorize, is_prime};
+pub use random::Rng;
@@ -0,0 +1,65 @@
+pub fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    ((a as u128 * b as u128) % modulus as u128) as u64
+}
+
+// square-and-multiply, one bit of the exponent at a time
+pub fn pow_mod(base: u64, exponent: u64, modulus: u64) -> u64 {
+    let mut result = 1 % modulus;
+    let mut base = base % modulus;
+    let mut exponent = exponent;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, modulus);
+        }
+        base = mul_mod(base, base, modulus);
+        exponent >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mul_mod, pow_mod};
+    use crate::Rng;
+
+    #[test]
+    fn test_pow_mod() {
+        assert_eq!(5764801, pow_mod(7, 8, 20201227));
+        assert_eq!(1, pow_mod(123, 0, 1000));
+        assert_eq!(0, pow_mod(123, 0, 1));
+        assert_eq!(0, pow_mod(0, 5, 7));
+
+        // close to 2^64, where a plain 64-bit product would overflow
+        let modulus = u64::MAX - 58; // the largest 64-bit prime
+        assert_eq!(1, pow_mod(3, modulus - 1, modulus));
+    }
+
+    #[test]
+    fn test_pow_mod_properties() {
+        let mut rng = Rng::new(25);
+        for _ in 0..2000 {
+            let modulus = rng.up_to(u64::MAX - 1);
+            let base = rng.next_u64();
+            let (a, b) = (rng.up_to(1 << 32), rng.up_to(1 << 32));
+
+            // x^(a+b) == x^a * x^b and x^(ab) == (x^a)^b
+            assert_eq!(
+                pow_mod(base, a + b, modulus),
+                mul_mod(
+                    pow_mod(base, a, modulus),
+                    pow_mod(base, b, modulus),
+                    modulus
+                )
+            );
+            assert_eq!(
+                pow_mod(base, a * b, modulus),
+                pow_mod(pow_mod(base, a, modulus), b, modulus)
+            );
+
+            // small exponents match repeated multiplication
+            let small = a % 20;
+            let repeated = (0..small).fold(1 % modulus, |value, _| mul_mod(value, base, modulus));
+            assert_eq!(repeated, pow_mod(base, small, modulus));
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{
+    euclid::gcd,
+    modular::{mul_mod, pow_mod},
+};
+
+// Miller-Rabin with the first twelve primes as witnesses, which is exact for every u64.
+pub fn is_prime(n: u64) -> bool {
+    const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    if let Some(&witness) = WITNESSES.iter().find(|&&witness| n.is_multiple_of(witness)) {
+        return n == witness;
+    }
+
+    let odd_part = (n - 1) >> (n - 1).trailing_zeros();
+    WITNESSES.iter().all(|&witness| {
+        let mut value = pow_mod(witness, odd_part, n);
+        let mut exponent = odd_part;
+        if value == 1 {
+            return true;
+        }
+        while exponent < n - 1 {
+            if value == n - 1 {
+                return true;
+            }
+            value = mul_mod(value, value, n);
+            exponent *= 2;
+        }
+        false
+    })
+}
+
+// Pollard's rho with Brent's cycle detection: some non-trivial factor of a composite n.
+fn find_factor(n: u64) -> u64 {
+    if n.is_multiple_of(2) {
+        return 2;
+    }
+
+    // a different polynomial x^2 + c for each attempt, in case one cycles without a factor
+    for c in 1.. {
+        let step = |x: u64| (mul_mod(x, x, n) + c) % n;
+        let (mut y, mut product) = (2u64, 1u64);
+        let mut length = 1;
+        let factor = loop {
+            let x = y;
+            for _ in 0..length {
+                y = step(y);
+            }
+
+            // batch the gcds by multiplying the differences together
+            let mut done = 0;
+            let mut factor = 1;
+            while done < length && factor == 1 {
+                let saved_y = y;
+                let batch = (length - done).min(128);
+                for _ in 0..batch {
+                    y = step(y);
+                    product = mul_mod(product, x.abs_diff(y), n);
+                }
+                factor = gcd(product as i128, n as i128) as u64;
+                if factor == n {
+                    // the batch overshot, so go back over it one step at a time
+                    y = saved_y;
+                    loop {
+                        y = step(y);
+                        factor = gcd(x.abs_diff(y) as i128, n as i128) as u64;
+                        if factor != 1 {
+                            break;
+                        }
+                    }
+                }
+                done += batch;
+            }
+            if factor != 1 {
+                break factor;
+            }
+            length *= 2;
+        };
+
+        if factor != n {
+            return factor;
+        }
+    }
+    unreachable!()
+}
+
+// Prime factors and their exponents, smallest first.
+pub fn factorize(n: u64) -> Vec<(u64, u32)> {
+    let mut primes = vec![];
+    let mut remaining = n;
+
+    // trial division takes care of the small factors quickly
+    for divisor in 2..1000u64 {
+        while remaining.is_multiple_of(divisor) {
+            primes.push(divisor);
+            remaining /= divisor;
+        }
+    }
+
+    let mut pending = if remaining > 1 {
+        vec![remaining]
+    } else {
+        vec![]
+    };
+    while let Some(number) = pending.pop() {
+        if is_prime(number) {
+            primes.push(number);
+        } else {
+            let factor = find_factor(number);
+            pending.push(factor);
+            pending.push(number / factor);
+        }
+    }
+
+    primes.sort_unstable();
+    let mut factors: Vec<(u64, u32)> = vec![];
+    for prime in primes {
+        match factors.last_mut() {
+            Some((last, exponent)) if *last == prime => *exponent += 1,
+            _ => factors.push((prime, 1)),
+        }
+    }
+    factors
+}
+
+pub fn euler_totient(n: u64) -> u64 {
+    factorize(n)
+        .iter()
+        .fold(n, |totient, &(prime, _)| totient / prime * (prime - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{euler_totient, factorize, is_prime};
+    use crate::{euclid::gcd, Rng};
+
+    #[test]
+    fn test_is_prime() {
+        let small_primes: Vec<u64> = (0..50).filter(|&n| is_prime(n)).collect();
+        assert_eq!(
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47],
+            small_primes
+        );
+        assert!(is_prime(20201227));
+        assert!(is_prime(u64::MAX - 58));
+        assert!(!is_prime(3215031751)); // a strong pseudoprime to bases 2, 3, 5 and 7
+        assert!(!is_prime(1_000_000_007 * 998_244_353));
+
+        // trial division agrees for everything small
+        for n in 0..5000u64 {
+            let trial = n >= 2 && (2..n).take_while(|d| d * d <= n).all(|d| n % d != 0);
+            assert_eq!(trial, is_prime(n), "{}", n);
+        }
+    }
+
+    #[test]
+    fn test_factorize() {
+        assert_eq!(Vec::<(u64, u32)>::new(), factorize(1));
+        assert_eq!(vec![(2, 1), (3, 3), (5, 1), (13, 1)], factorize(3510));
+        assert_eq!(vec![(20201227, 1)], factorize(20201227));
+        assert_eq!(
+            vec![(998_244_353, 1), (1_000_000_007, 1)],
+            factorize(1_000_000_007 * 998_244_353)
+        );
+        assert_eq!(
+            vec![(4_294_967_291, 2)],
+            factorize(4_294_967_291 * 4_294_967_291)
+        );
+        assert_eq!(20201226, euler_totient(20201227));
+        assert_eq!(4, euler_totient(12));
+    }
+
+    #[test]
+    fn test_factorize_properties() {
+        let mut rng = Rng::new(49);
+        for _ in 0..300 {
+            let n = rng.up_to(u64::MAX - 1);
+            let factors = factorize(n);
+
+            let product = factors.iter().fold(1u64, |product, &(prime, exponent)| {
+                product * prime.pow(exponent)
+            });
+            assert_eq!(n, product);
+            assert!(factors.iter().all(|&(prime, _)| is_prime(prime)));
+            assert!(factors.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        }
+
+        // totient counts the numbers coprime to n
+        for n in 1..300u64 {
+            let coprime = (1..=n).filter(|&k| gcd(k as i128, n as i128) == 1).count();
+            assert_eq!(coprime as u64, euler_totient(n));
+        }
+    }
+}
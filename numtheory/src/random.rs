@@ -0,0 +1,90 @@
+// One step of splitmix64, which turns nearby inputs into unrelated outputs.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// xorshift64*, for generating puzzles and property tests without any dependencies.
+// Seeds are scrambled with splitmix64 first, so that nearby seeds give unrelated streams.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        let state = splitmix64(seed);
+        // xorshift can never leave the all-zeroes state, and exactly one seed scrambles to it
+        Rng {
+            state: if state == 0 {
+                0x9e37_79b9_7f4a_7c15
+            } else {
+                state
+            },
+        }
+    }
+
+    // One of many independent streams from the same seed, such as one per simulated game.
+    pub fn for_stream(seed: u64, stream: u64) -> Rng {
+        Rng::new(splitmix64(seed) ^ stream)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    // A number in 0..bound.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0);
+        self.next_u64() % bound
+    }
+
+    // An index into something of the given length.
+    pub fn index(&mut self, len: usize) -> usize {
+        self.below(len as u64) as usize
+    }
+
+    pub fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    // A number in 1..=bound, more often small, so tests hit both edge cases and big values.
+    pub fn up_to(&mut self, bound: u64) -> u64 {
+        let bits = self.next_u64() % 64 + 1;
+        let value = self.next_u64() >> (64 - bits);
+        value % bound + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rng;
+
+    #[test]
+    fn test_no_seed_gets_stuck() {
+        // splitmix64 sends this seed to zero
+        let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15u64.wrapping_neg());
+        assert!((0..4).any(|_| rng.next_u64() != 0));
+    }
+
+    #[test]
+    fn test_nearby_seeds_are_unrelated() {
+        // unrelated outputs differ in about half their bits
+        for seed in 0..1000 {
+            let first = Rng::new(seed).next_u64();
+            let second = Rng::new(seed + 1).next_u64();
+            let differing_bits = (first ^ second).count_ones();
+            assert!((12..=52).contains(&differing_bits), "seed {}", seed);
+
+            let first = Rng::for_stream(1, seed).next_u64();
+            let second = Rng::for_stream(1, seed + 1).next_u64();
+            let differing_bits = (first ^ second).count_ones();
+            assert!((12..=52).contains(&differing_bits), "stream {}", seed);
+        }
+    }
+}
@@ -0,0 +1,32 @@
+// Helpers for writing the puzzles' results as JSON without any dependencies.
+
+// A JSON string literal for the value, quotes included.
+pub fn json_string(value: &str) -> String {
+    let mut result = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::json_string;
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!("\"departure row\"", json_string("departure row"));
+        assert_eq!(
+            "\"a \\\"b\\\" c\\\\d\\ne\\u0009\"",
+            json_string("a \"b\" c\\d\ne\t")
+        );
+        assert_eq!("\"sésame\"", json_string("sésame"));
+    }
+}